@@ -0,0 +1,236 @@
+use crate::validate::excerpt;
+use regex::Regex;
+use serde_json::Value;
+
+/// 方法结果的期望值断言
+///
+/// 与 [`crate::validate::Validator`] 只检查结果格式不同，断言检查结果的具体值，例如 BSC 上的
+/// `eth_chainId` 必须为 `0x38`。断言失败不影响调用是否成功和延迟统计，单独报告并使进程以失败状态退出。
+#[derive(Debug, Clone)]
+pub struct Assertion {
+    /// 只在该网络的端点上检查（例如 BSC），None 表示所有网络
+    network: Option<String>,
+    /// 检查的值在 `result` 中的路径，例如 `$.baseFeePerGas[0]`；None 表示整个 `result`
+    path: Option<String>,
+    expectation: Expectation,
+}
+
+/// 断言对值的要求
+#[derive(Debug, Clone)]
+enum Expectation {
+    /// 与期望值完全相同，十六进制字符串不区分大小写
+    Equals(Value),
+    /// 字符串匹配正则表达式
+    Matches(Regex),
+    /// 按 ABI 解码为字符串后与期望值相同（例如 ERC-20 的 `name()`）
+    AbiString(String),
+    /// 路径存在且不为 null
+    Exists,
+}
+
+impl Assertion {
+    /// 结果必须等于 `expected`
+    pub fn equals(expected: Value) -> Self {
+        Self::new(Expectation::Equals(expected))
+    }
+
+    /// 结果必须是匹配 `pattern` 的字符串
+    ///
+    /// 正则表达式写在方法定义中，无效时 panic。
+    pub fn matches(pattern: &str) -> Self {
+        let regex = Regex::new(pattern).unwrap_or_else(|e| panic!("无效的断言正则表达式 {}: {}", pattern, e));
+        Self::new(Expectation::Matches(regex))
+    }
+
+    /// 结果必须是 ABI 编码的字符串 `expected`
+    pub fn abi_string(expected: &str) -> Self {
+        Self::new(Expectation::AbiString(expected.to_string()))
+    }
+
+    /// 路径指向的值必须存在且不为 null，与 [`Assertion::at`] 一起使用
+    pub fn exists() -> Self {
+        Self::new(Expectation::Exists)
+    }
+
+    fn new(expectation: Expectation) -> Self {
+        Self {
+            network: None,
+            path: None,
+            expectation,
+        }
+    }
+
+    /// 检查 `result` 中 `path` 指向的值，路径格式为 `$`、`$.field`、`$.list[0].field`
+    pub fn at(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// 只在属于 `network` 的端点上检查
+    pub fn on(mut self, network: &str) -> Self {
+        self.network = Some(network.to_string());
+        self
+    }
+
+    /// 断言是否适用于该网络的端点
+    pub fn applies_to(&self, network: &str) -> bool {
+        self.network.as_deref().is_none_or(|n| n == network)
+    }
+
+    /// 报告中显示的断言内容，例如 `$.oldestBlock exists`
+    pub fn describe(&self) -> String {
+        let path = self.path.as_deref().unwrap_or("result");
+        match &self.expectation {
+            Expectation::Equals(expected) => format!("{} == {}", path, expected),
+            Expectation::Matches(regex) => format!("{} =~ /{}/", path, regex.as_str()),
+            Expectation::AbiString(expected) => format!("abi_string({}) == {:?}", path, expected),
+            Expectation::Exists => format!("{} exists", path),
+        }
+    }
+
+    /// 检查 `result`，失败时返回原因
+    pub fn check(&self, result: &Value) -> Result<(), String> {
+        let value = match &self.path {
+            Some(path) => select(result, path)?,
+            None => result,
+        };
+        let passed = match &self.expectation {
+            Expectation::Equals(expected) => match (expected, value) {
+                (Value::String(a), Value::String(b)) if a.starts_with("0x") => a.eq_ignore_ascii_case(b),
+                _ => expected == value,
+            },
+            Expectation::Matches(regex) => value.as_str().is_some_and(|s| regex.is_match(s)),
+            Expectation::AbiString(expected) => {
+                let decoded = value.as_str().and_then(decode_abi_string);
+                if decoded.as_deref() != Some(expected.as_str()) {
+                    return Err(match decoded {
+                        Some(decoded) => format!("{} 不成立，实际解码为 {:?}", self.describe(), decoded),
+                        None => format!("{} 不成立，无法解码: {}", self.describe(), excerpt(value)),
+                    });
+                }
+                true
+            }
+            Expectation::Exists => !value.is_null(),
+        };
+        if passed {
+            Ok(())
+        } else {
+            Err(format!("{} 不成立，实际为 {}", self.describe(), excerpt(value)))
+        }
+    }
+}
+
+/// 按路径取出 `result` 中的值，路径不存在时返回错误
+fn select<'a>(result: &'a Value, path: &str) -> Result<&'a Value, String> {
+    let missing = || format!("路径 {} 不存在", path);
+    let mut rest = path.strip_prefix('$').ok_or_else(|| format!("断言路径必须以 $ 开头: {}", path))?;
+    let mut current = result;
+    while !rest.is_empty() {
+        if let Some(index) = rest.strip_prefix('[') {
+            let (index, remaining) = index.split_once(']').ok_or_else(|| format!("断言路径无效: {}", path))?;
+            let index: usize = index.parse().map_err(|_| format!("断言路径中的下标无效: {}", path))?;
+            current = current.get(index).ok_or_else(missing)?;
+            rest = remaining;
+        } else if let Some(field) = rest.strip_prefix('.') {
+            let end = field.find(['.', '[']).unwrap_or(field.len());
+            current = current.get(&field[..end]).ok_or_else(missing)?;
+            rest = &field[end..];
+        } else {
+            return Err(format!("断言路径无效: {}", path));
+        }
+    }
+    Ok(current)
+}
+
+/// 解码 ABI 编码的 `string` 返回值：32 字节偏移量、32 字节长度，然后是 UTF-8 内容
+fn decode_abi_string(data: &str) -> Option<String> {
+    let hex = data.strip_prefix("0x")?;
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    let word = |offset: usize| -> Option<usize> {
+        let word = bytes.get(offset..offset.checked_add(32)?)?;
+        // 偏移量和长度不会超过 8 字节，高位必须为零
+        if word[..24].iter().any(|&b| b != 0) {
+            return None;
+        }
+        Some(word[24..].iter().fold(0usize, |acc, &b| (acc << 8) | b as usize))
+    };
+    let offset = word(0)?;
+    let len = word(offset)?;
+    let content = bytes.get(offset + 32..offset.checked_add(32)?.checked_add(len)?)?;
+    String::from_utf8(content.to_vec()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// DAI 合约 `name()` 的返回值 "Dai Stablecoin"
+    const DAI_NAME: &str = "0x0000000000000000000000000000000000000000000000000000000000000020\
+                            000000000000000000000000000000000000000000000000000000000000000e\
+                            44616920537461626c65636f696e000000000000000000000000000000000000";
+
+    #[test]
+    fn select_follows_fields_and_indices() {
+        let value = json!({ "a": [{ "b": 1 }, { "b": 2 }] });
+        assert_eq!(select(&value, "$.a[1].b"), Ok(&json!(2)));
+        assert_eq!(select(&value, "$"), Ok(&value));
+    }
+
+    #[test]
+    fn select_reports_missing_path() {
+        let value = json!({ "a": [{ "b": 1 }] });
+        assert!(select(&value, "$.a[0].c").unwrap_err().contains("不存在"));
+        assert!(select(&value, "$.a[5]").unwrap_err().contains("不存在"));
+    }
+
+    #[test]
+    fn select_rejects_bad_index_and_missing_root() {
+        let value = json!({ "a": [1] });
+        assert!(select(&value, "$.a[x]").unwrap_err().contains("下标无效"));
+        assert!(select(&value, "$.a[0").unwrap_err().contains("路径无效"));
+        assert!(select(&value, "a[0]").unwrap_err().contains("$ 开头"));
+    }
+
+    #[test]
+    fn decodes_abi_string() {
+        assert_eq!(decode_abi_string(DAI_NAME).as_deref(), Some("Dai Stablecoin"));
+    }
+
+    #[test]
+    fn malformed_abi_string_returns_none() {
+        assert_eq!(decode_abi_string(&DAI_NAME[..DAI_NAME.len() - 64]), None);
+        assert_eq!(decode_abi_string(&DAI_NAME[..DAI_NAME.len() - 1]), None);
+        assert_eq!(decode_abi_string("0x"), None);
+        assert_eq!(decode_abi_string(DAI_NAME.trim_start_matches("0x")), None);
+        // 偏移量超出 usize 范围
+        assert_eq!(decode_abi_string(&format!("0x{}", "ff".repeat(64))), None);
+    }
+
+    #[test]
+    fn check_applies_expectations() {
+        assert_eq!(Assertion::equals(json!("0x38")).check(&json!("0x38")), Ok(()));
+        assert_eq!(Assertion::equals(json!("0xAB")).check(&json!("0xab")), Ok(()));
+        assert!(Assertion::equals(json!("0x38")).check(&json!("0x1")).is_err());
+        assert_eq!(Assertion::matches(r"^Geth/").check(&json!("Geth/v1.13.0")), Ok(()));
+        assert!(Assertion::matches(r"^Geth/").check(&json!(1)).is_err());
+        assert_eq!(Assertion::abi_string("Dai Stablecoin").check(&json!(DAI_NAME)), Ok(()));
+        assert!(Assertion::abi_string("USD Coin").check(&json!(DAI_NAME)).unwrap_err().contains("Dai Stablecoin"));
+        assert_eq!(Assertion::exists().at("$.fees[1]").check(&json!({ "fees": ["0x1", "0x2"] })), Ok(()));
+        assert!(Assertion::exists().at("$.fees[1]").check(&json!({ "fees": ["0x1"] })).is_err());
+    }
+
+    #[test]
+    fn applies_only_to_its_network() {
+        let assertion = Assertion::equals(json!("0x38")).on("BSC");
+        assert!(assertion.applies_to("BSC"));
+        assert!(!assertion.applies_to("ETH"));
+        assert!(Assertion::exists().applies_to("ETH"));
+    }
+}
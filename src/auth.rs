@@ -0,0 +1,99 @@
+use crate::chains::{check_endpoint_names, split_endpoint_spec, Chain, ConnectionType};
+use anyhow::{Context, Result};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use std::collections::HashMap;
+
+/// Engine API 使用的 256 位 JWT 密钥（HS256）
+#[derive(Clone)]
+pub struct JwtSecret {
+    key: [u8; 32],
+}
+
+impl std::fmt::Debug for JwtSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("JwtSecret(***)")
+    }
+}
+
+impl JwtSecret {
+    /// 读取密钥文件，内容为 64 位十六进制字符串，可带 `0x` 前缀（与执行客户端的 `jwt.hex` 格式相同）
+    pub fn load(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("读取JWT密钥失败: {}", path))?;
+        let hex = content.trim();
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            anyhow::bail!("无效的JWT密钥 (需要64位十六进制字符串): {}", path);
+        }
+        let mut key = [0u8; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)?;
+        }
+        Ok(Self { key })
+    }
+
+    /// 以当前时间为 `iat` 签发一个 HS256 令牌
+    ///
+    /// 执行客户端只接受 `iat` 与本地时间相差 60 秒以内的令牌，因此每个请求都重新签发。
+    pub fn token(&self) -> String {
+        let encode = |data: &[u8]| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(data);
+        let iat = chrono::Utc::now().timestamp();
+        let signing_input = format!(
+            "{}.{}",
+            encode(json!({ "alg": "HS256", "typ": "JWT" }).to_string().as_bytes()),
+            encode(json!({ "iat": iat }).to_string().as_bytes())
+        );
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC 接受任意长度的密钥");
+        mac.update(signing_input.as_bytes());
+        format!("{}.{}", signing_input, encode(&mac.finalize().into_bytes()))
+    }
+
+    /// Authorization 请求头的值
+    pub fn authorization(&self) -> String {
+        format!("Bearer {}", self.token())
+    }
+}
+
+/// 解析 `--jwt-secret` 参数并设置到 Engine API 端点
+///
+/// 参数格式为 `路径`（所有 Engine API 端点）或 `端点名称=路径`，按名称指定的密钥优先。
+/// 每个 Engine API 端点都必须有密钥。
+pub fn apply_jwt_specs(chains: &mut [Chain], specs: &[String]) -> Result<()> {
+    let mut default = None;
+    let mut by_endpoint = HashMap::new();
+    for spec in specs {
+        match split_endpoint_spec(spec) {
+            (Some(name), path) => {
+                if by_endpoint.insert(name.to_string(), JwtSecret::load(path)?).is_some() {
+                    anyhow::bail!("端点 {} 重复指定了JWT密钥", name);
+                }
+            }
+            (None, path) => {
+                if default.replace(JwtSecret::load(path)?).is_some() {
+                    anyhow::bail!("默认JWT密钥只能指定一个");
+                }
+            }
+        }
+    }
+
+    check_endpoint_names(chains, by_endpoint.keys().map(|k| k.as_str()), "--jwt-secret")?;
+    if let Some(chain) = chains
+        .iter()
+        .find(|c| by_endpoint.contains_key(&c.name) && c.connection_type != ConnectionType::Engine)
+    {
+        anyhow::bail!("--jwt-secret 只能用于 Engine API 端点: {}", chain.name);
+    }
+
+    for chain in chains.iter_mut().filter(|c| c.connection_type == ConnectionType::Engine) {
+        let secret = by_endpoint.remove(&chain.name).or_else(|| default.clone());
+        if secret.is_none() {
+            anyhow::bail!("Engine API 端点 {} 需要通过 --jwt-secret 指定JWT密钥", chain.name);
+        }
+        chain.jwt_secret = secret;
+    }
+
+    Ok(())
+}
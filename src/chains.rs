@@ -1,3 +1,7 @@
+use crate::auth::JwtSecret;
+use crate::net::ProxyConfig;
+use crate::tls::TlsOptions;
+use anyhow::Result;
 use log::debug;
 
 /// RPC 连接类型
@@ -7,6 +11,27 @@ pub enum ConnectionType {
     Http,
     /// WebSocket 连接
     WebSocket,
+    /// EIP-1767 GraphQL 接口 (HTTP/HTTPS)
+    GraphQl,
+    /// 需要 JWT 认证的 Engine API 端口 (HTTP/HTTPS)
+    Engine,
+}
+
+impl ConnectionType {
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConnectionType::Http => "HTTP",
+            ConnectionType::WebSocket => "WebSocket",
+            ConnectionType::GraphQl => "GraphQL",
+            ConnectionType::Engine => "Engine",
+        }
+    }
+
+    /// 是否通过 HTTP 请求访问
+    pub fn is_http(&self) -> bool {
+        matches!(self, ConnectionType::Http | ConnectionType::GraphQl | ConnectionType::Engine)
+    }
 }
 
 /// Chain 表示一条区块链及其 RPC 端点
@@ -18,24 +43,26 @@ pub struct Chain {
     pub rpc_url: String,
     /// 连接类型 (HTTP 或 WebSocket)
     pub connection_type: ConnectionType,
+    /// 连接端点使用的代理，None 表示直连
+    pub proxy: Option<ProxyConfig>,
+    /// 连接端点使用的 TLS 配置
+    pub tls: TlsOptions,
+    /// Engine API 的 JWT 密钥，设置后每个请求都携带新签发的令牌
+    pub jwt_secret: Option<JwtSecret>,
+    /// 每秒请求数上限，None 表示不限速
+    pub rate_limit: Option<f64>,
 }
 
 impl Chain {
     /// 创建一个新的链配置
     pub fn new(name: &str, rpc_url: &str) -> Self {
         // 根据URL自动确定连接类型
-        let connection_type = if rpc_url.starts_with("ws://") || rpc_url.starts_with("wss://") {
+        let chain = if rpc_url.starts_with("ws://") || rpc_url.starts_with("wss://") {
             debug!("检测到WebSocket URL: {}", rpc_url);
-            ConnectionType::WebSocket
+            Self::new_ws(name, rpc_url)
         } else {
             debug!("检测到HTTP URL: {}", rpc_url);
-            ConnectionType::Http
-        };
-
-        let chain = Self {
-            name: name.to_string(),
-            rpc_url: rpc_url.to_string(),
-            connection_type,
+            Self::new_http(name, rpc_url)
         };
         
         debug!("创建链 {}: {} (连接类型: {:?})", 
@@ -44,12 +71,42 @@ impl Chain {
         chain
     }
 
+    /// 链所属的网络 (例如 "ETH-WS-2" 属于 "ETH")
+    pub fn network(&self) -> &str {
+        self.name.split('-').next().unwrap_or(&self.name)
+    }
+
+    /// 报告中显示的代理地址（隐去密码），直连时为空
+    pub fn proxy_label(&self) -> String {
+        self.proxy.as_ref().map(|p| p.redacted()).unwrap_or_default()
+    }
+
     /// 创建一个新的HTTP链配置
     pub fn new_http(name: &str, rpc_url: &str) -> Self {
         Self {
             name: name.to_string(),
             rpc_url: rpc_url.to_string(),
             connection_type: ConnectionType::Http,
+            proxy: None,
+            tls: TlsOptions::default(),
+            jwt_secret: None,
+            rate_limit: None,
+        }
+    }
+
+    /// 创建一个新的GraphQL链配置
+    pub fn new_graphql(name: &str, rpc_url: &str) -> Self {
+        Self {
+            connection_type: ConnectionType::GraphQl,
+            ..Self::new_http(name, rpc_url)
+        }
+    }
+
+    /// 创建一个新的Engine API链配置
+    pub fn new_engine(name: &str, rpc_url: &str) -> Self {
+        Self {
+            connection_type: ConnectionType::Engine,
+            ..Self::new_http(name, rpc_url)
         }
     }
 
@@ -59,6 +116,32 @@ impl Chain {
             name: name.to_string(),
             rpc_url: rpc_url.to_string(),
             connection_type: ConnectionType::WebSocket,
+            proxy: None,
+            tls: TlsOptions::default(),
+            jwt_secret: None,
+            rate_limit: None,
         }
     }
-}
\ No newline at end of file
+}
+
+/// 拆分 `端点名称=值` 形式的命令行参数，未指定端点名称时返回 `(None, 值)`
+///
+/// 端点名称只能包含字母、数字和 `-`，因此 URL 和文件路径中的 `=` 不会被误认为分隔符。
+pub fn split_endpoint_spec(spec: &str) -> (Option<&str>, &str) {
+    match spec.split_once('=') {
+        Some((name, value)) if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') => {
+            (Some(name), value)
+        }
+        _ => (None, spec),
+    }
+}
+
+/// 检查参数中指定的端点名称都存在
+pub fn check_endpoint_names<'a>(chains: &[Chain], names: impl IntoIterator<Item = &'a str>, option: &str) -> Result<()> {
+    for name in names {
+        if !chains.iter().any(|c| c.name == name) {
+            anyhow::bail!("{} 指定的端点不存在: {}", option, name);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use crate::rpc::RpcError;
+use anyhow::Result;
+use std::io::Read;
+
+/// HTTP 响应压缩协商方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// 不发送 Accept-Encoding，由节点返回未压缩的响应（默认）
+    Identity,
+    /// 只接受 gzip
+    Gzip,
+    /// 只接受 brotli
+    Brotli,
+    /// 只接受 zstd
+    Zstd,
+    /// 同时接受 gzip、brotli 和 zstd，由节点选择
+    Auto,
+}
+
+impl Compression {
+    /// 压缩对比测试中依次使用的编码
+    pub const COMPARED: [Compression; 4] = [
+        Compression::Identity,
+        Compression::Gzip,
+        Compression::Brotli,
+        Compression::Zstd,
+    ];
+
+    /// 命令行和报告中使用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::Identity => "none",
+            Compression::Gzip => "gzip",
+            Compression::Brotli => "br",
+            Compression::Zstd => "zstd",
+            Compression::Auto => "auto",
+        }
+    }
+
+    /// 请求中发送的 Accept-Encoding 头，不压缩时不发送
+    pub fn accept_encoding(&self) -> Option<&'static str> {
+        match self {
+            Compression::Identity => None,
+            Compression::Gzip => Some("gzip"),
+            Compression::Brotli => Some("br"),
+            Compression::Zstd => Some("zstd"),
+            Compression::Auto => Some("gzip, br, zstd"),
+        }
+    }
+}
+
+impl std::str::FromStr for Compression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "none" | "identity" => Ok(Compression::Identity),
+            "gzip" => Ok(Compression::Gzip),
+            "br" | "brotli" => Ok(Compression::Brotli),
+            "zstd" => Ok(Compression::Zstd),
+            "auto" => Ok(Compression::Auto),
+            _ => anyhow::bail!("无效的压缩方式: {}。支持的方式: none, gzip, br, zstd, auto", s),
+        }
+    }
+}
+
+/// 按 Content-Encoding 解压响应体，未压缩的响应原样返回
+pub fn decode_body(content_encoding: Option<&str>, body: Vec<u8>) -> Result<Vec<u8>, RpcError> {
+    let encoding = match content_encoding.map(|e| e.trim().to_lowercase()) {
+        None => return Ok(body),
+        Some(encoding) if encoding.is_empty() || encoding == "identity" => return Ok(body),
+        Some(encoding) => encoding,
+    };
+
+    let mut decoded = Vec::with_capacity(body.len() * 4);
+    let result = match encoding.as_str() {
+        "gzip" | "x-gzip" => flate2::read::GzDecoder::new(body.as_slice()).read_to_end(&mut decoded),
+        "deflate" => flate2::read::ZlibDecoder::new(body.as_slice()).read_to_end(&mut decoded),
+        "br" => brotli::Decompressor::new(body.as_slice(), 4096).read_to_end(&mut decoded),
+        "zstd" => zstd::stream::read::Decoder::new(body.as_slice())
+            .and_then(|mut decoder| decoder.read_to_end(&mut decoded)),
+        _ => return Err(RpcError::NetworkError(format!("不支持的内容编码: {}", encoding))),
+    };
+
+    result.map_err(|e| RpcError::NetworkError(format!("解压{}响应失败: {}", encoding, e)))?;
+    Ok(decoded)
+}
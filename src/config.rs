@@ -0,0 +1,103 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// `--config` 指定的 TOML 配置文件，命令行参数优先于文件中的值
+///
+/// ```toml
+/// http_timeout_secs = 10
+/// ws_timeout_secs = 15
+/// request_delay_ms = 100
+/// request_jitter_ms = 50
+/// max_concurrent_requests = 10
+/// deadline_secs = 600
+/// rate_limit_burst = 5
+/// warmup = 3
+/// retry_attempts = 3
+/// retry_backoff_ms = 200
+/// retry_max_backoff_ms = 5000
+/// retry_on = "network,timeout"
+///
+/// [method_timeouts]
+/// eth_getLogs = 30
+///
+/// [rate_limits]
+/// ETH-HTTP-2 = 25
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub http_timeout_secs: Option<u64>,
+    pub ws_timeout_secs: Option<u64>,
+    pub request_delay_ms: Option<u64>,
+    pub request_jitter_ms: Option<u64>,
+    pub max_concurrent_requests: Option<usize>,
+    /// 整个测试的时间上限（秒）
+    pub deadline_secs: Option<u64>,
+    /// 限速令牌桶容量
+    pub rate_limit_burst: Option<u32>,
+    /// 每个方法的预热请求次数
+    pub warmup: Option<usize>,
+    pub retry_attempts: Option<u32>,
+    pub retry_backoff_ms: Option<u64>,
+    pub retry_max_backoff_ms: Option<u64>,
+    /// 可重试的错误类型，与 `--retry-on` 格式相同
+    pub retry_on: Option<String>,
+    /// 按方法名称覆盖的请求超时（秒）
+    #[serde(default)]
+    pub method_timeouts: HashMap<String, u64>,
+    /// 按端点名称指定的每秒请求数上限
+    #[serde(default)]
+    pub rate_limits: HashMap<String, f64>,
+}
+
+impl FileConfig {
+    /// 读取并解析配置文件
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("读取配置文件失败: {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("配置文件格式无效: {}", path.display()))
+    }
+}
+
+/// 解析 `--method-timeout` 参数，格式为 `方法名=秒数`
+pub fn parse_method_timeouts(specs: &[String]) -> Result<HashMap<String, u64>> {
+    specs
+        .iter()
+        .map(|spec| {
+            let (method, secs) = spec
+                .split_once('=')
+                .with_context(|| format!("方法超时格式无效 (需要 方法名=秒数): {}", spec))?;
+            let secs: u64 = secs
+                .trim()
+                .parse()
+                .with_context(|| format!("方法超时必须是秒数: {}", spec))?;
+            if secs == 0 {
+                anyhow::bail!("方法超时必须大于0秒: {}", spec);
+            }
+            Ok((method.trim().to_string(), secs))
+        })
+        .collect()
+}
+
+/// 解析时长参数：不带单位时为秒数，也可以使用 s、m、h、d 后缀 (例如 90m、24h)
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let (number, unit_secs) = match value.char_indices().last() {
+        Some((i, 's')) => (&value[..i], 1),
+        Some((i, 'm')) => (&value[..i], 60),
+        Some((i, 'h')) => (&value[..i], 3600),
+        Some((i, 'd')) => (&value[..i], 86400),
+        _ => (value, 1),
+    };
+    let number: u64 = number
+        .trim()
+        .parse()
+        .with_context(|| format!("无效的时长: {} (例如 3600、90m、24h)", value))?;
+    if number == 0 {
+        anyhow::bail!("时长必须大于0: {}", value);
+    }
+    Ok(Duration::from_secs(number.saturating_mul(unit_secs)))
+}
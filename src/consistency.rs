@@ -0,0 +1,604 @@
+use crate::chains::{split_endpoint_spec, Chain, ConnectionType};
+use crate::methods::{test_addresses::*, RpcMethod};
+use crate::rpc::{Config, RpcManager};
+use crate::validate::{excerpt, parse_quantity};
+use anyhow::{Context, Result};
+use futures::future;
+use itertools::Itertools;
+use log::{info, warn};
+use prettytable::{format, Cell, Row, Table};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+/// 每个比较结果中最多列出的差异数
+const MAX_DIFFS: usize = 5;
+
+/// 比较区块时保留的字段，其余字段（例如 totalDifficulty、size）在不同客户端间本就可能不同
+const BLOCK_FIELDS: [&str; 12] = [
+    "number",
+    "hash",
+    "parentHash",
+    "timestamp",
+    "miner",
+    "stateRoot",
+    "transactionsRoot",
+    "receiptsRoot",
+    "gasUsed",
+    "gasLimit",
+    "baseFeePerGas",
+    "transactions",
+];
+
+/// 比较日志时保留的字段
+const LOG_FIELDS: [&str; 8] = [
+    "address",
+    "topics",
+    "data",
+    "blockNumber",
+    "blockHash",
+    "transactionHash",
+    "transactionIndex",
+    "logIndex",
+];
+
+/// 一致性检查的配置
+#[derive(Debug, Clone, Default)]
+pub struct ConsistencyConfig {
+    /// 所有网络使用的固定区块号
+    pub block: Option<u64>,
+    /// 按网络指定的固定区块号，优先于 `block`
+    pub network_blocks: HashMap<String, u64>,
+    /// 未指定区块号时，使用各端点最新区块的最大值减去该深度，避开尚未在所有端点上确定的区块
+    pub depth: u64,
+}
+
+impl ConsistencyConfig {
+    /// 解析 `--consistency-block` 参数，格式为 `区块号` 或 `网络名称=区块号`
+    pub fn new(specs: &[String], depth: u64) -> Result<Self> {
+        let mut config = Self {
+            depth,
+            ..Self::default()
+        };
+        for spec in specs {
+            match split_endpoint_spec(spec) {
+                (Some(network), block) => {
+                    if config.network_blocks.insert(network.to_uppercase(), parse_block(block)?).is_some() {
+                        anyhow::bail!("网络 {} 重复指定了一致性检查区块", network);
+                    }
+                }
+                (None, block) => {
+                    if config.block.replace(parse_block(block)?).is_some() {
+                        anyhow::bail!("默认一致性检查区块只能指定一个");
+                    }
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+fn parse_block(value: &str) -> Result<u64> {
+    let value = value.trim();
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+    .with_context(|| format!("区块号必须是十进制或 0x 开头的十六进制数: {}", value))
+}
+
+/// 一个端点在一次查询中的结果与多数结果的比较
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Agreement {
+    /// 与多数结果相同
+    Agree,
+    /// 与多数结果不同
+    Disagree,
+    /// 出现次数最多的结果不止一个，无法判断哪个端点出错
+    NoMajority,
+    /// 请求失败，不参与比较
+    Error,
+}
+
+impl Agreement {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Agreement::Agree => "agree",
+            Agreement::Disagree => "disagree",
+            Agreement::NoMajority => "no_majority",
+            Agreement::Error => "error",
+        }
+    }
+}
+
+/// 一个端点在一次查询中的一致性检查结果
+#[derive(Debug, Clone)]
+pub struct ConsistencyResult {
+    pub network: String,
+    pub block: u64,
+    pub method: String,
+    pub chain: String,
+    pub endpoint: String,
+    pub agreement: Agreement,
+    /// 返回多数结果的端点数
+    pub majority_count: usize,
+    /// 参与比较的端点数（包括失败的端点）
+    pub endpoint_count: usize,
+    /// 与多数结果的差异或错误信息
+    pub detail: String,
+}
+
+/// 在固定区块上比较的确定性查询
+///
+/// 账户和合约按网络选取，使查询在该网络上有实际数据；未知网络使用以太坊上的地址。
+fn queries(network: &str, block: u64) -> Vec<RpcMethod> {
+    let block = format!("0x{:x}", block);
+    let (account, contract) = match network {
+        "BSC" => (BSC_HOT_WALLET, WBNB_CONTRACT),
+        _ => (VITALIK_ADDRESS, DAI_CONTRACT),
+    };
+    vec![
+        RpcMethod::new("eth_getBlockByNumber", vec![json!(block), json!(false)], "区块哈希和状态根"),
+        RpcMethod::new("eth_getBalance", vec![json!(account), json!(block)], "账户余额"),
+        RpcMethod::new("eth_getTransactionCount", vec![json!(account), json!(block)], "账户 nonce"),
+        RpcMethod::new(
+            "eth_getStorageAt",
+            vec![json!(contract), json!(format!("0x{:064x}", 0)), json!(block)],
+            "合约存储",
+        ),
+        RpcMethod::new("eth_getCode", vec![json!(contract), json!(block)], "合约代码"),
+        RpcMethod::new(
+            "eth_call",
+            vec![
+                json!({
+                    "to": contract,
+                    "data": "0x06fdde03" // name()
+                }),
+                json!(block),
+            ],
+            "合约调用",
+        ),
+        RpcMethod::new(
+            "eth_getLogs",
+            vec![json!({
+                "fromBlock": block,
+                "toBlock": block,
+                "address": contract
+            })],
+            "事件日志",
+        ),
+    ]
+}
+
+/// 对同一网络的所有 JSON-RPC 端点执行一致性检查
+///
+/// 端点按网络分组（GraphQL 和 Engine API 端点不参与），只有一个端点的网络跳过。
+/// 每个查询同时发往组内所有端点，结果规范化后与多数结果比较。
+pub async fn run_consistency_check(
+    chains: &[Chain],
+    consistency: &ConsistencyConfig,
+    config: &Config,
+) -> Result<Vec<ConsistencyResult>> {
+    let groups = chains
+        .iter()
+        .filter(|c| matches!(c.connection_type, ConnectionType::Http | ConnectionType::WebSocket))
+        .into_group_map_by(|c| c.network().to_string());
+
+    let mut results = Vec::new();
+    for (network, group) in groups.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+        if group.len() < 2 {
+            println!("{} 只有一个端点，跳过一致性检查", network);
+            continue;
+        }
+
+        let mut managers = Vec::new();
+        for chain in &group {
+            managers.push(RpcManager::new(config.clone(), std::slice::from_ref(*chain))?);
+        }
+
+        let pinned = match consistency.network_blocks.get(&network).or(consistency.block.as_ref()) {
+            Some(&block) => Some(block),
+            None => latest_block(&mut managers, &group).await.map(|head| head.saturating_sub(consistency.depth)),
+        };
+        let Some(block) = pinned else {
+            warn!("{} 的所有端点都无法返回最新区块，跳过一致性检查", network);
+            println!("{} 的所有端点都无法返回最新区块，跳过一致性检查", network);
+            close_all(managers).await;
+            continue;
+        };
+
+        println!("一致性检查: {} ({} 个端点), 区块 {} (0x{:x})", network, group.len(), block, block);
+        info!("开始一致性检查: {} 区块 {}", network, block);
+
+        for method in queries(&network, block) {
+            let responses = future::join_all(
+                managers.iter_mut().zip(&group).map(|(manager, chain)| manager.call(chain, &method)),
+            )
+            .await;
+            let values: Vec<Result<Value, String>> = responses
+                .into_iter()
+                .map(|response| response.map(|value| normalize(&method.name, &value)).map_err(|e| e.to_string()))
+                .collect();
+
+            let method_results = compare(&network, block, &method.name, &group, &values);
+            let disagreeing = method_results.iter().filter(|r| r.agreement != Agreement::Agree).count();
+            println!(
+                "  {:<28} {}/{} 一致{}",
+                method.name,
+                group.len() - disagreeing,
+                group.len(),
+                if disagreeing > 0 { " ✗" } else { "" }
+            );
+            results.extend(method_results);
+        }
+
+        close_all(managers).await;
+    }
+
+    Ok(results)
+}
+
+/// 各端点最新区块号的最大值，所有端点都失败时返回 None
+async fn latest_block(managers: &mut [RpcManager], group: &[&Chain]) -> Option<u64> {
+    let method = RpcMethod::new("eth_blockNumber", vec![], "获取最新区块号");
+    let responses = future::join_all(
+        managers.iter_mut().zip(group).map(|(manager, chain)| manager.call(chain, &method)),
+    )
+    .await;
+    responses
+        .iter()
+        .zip(group)
+        .filter_map(|(response, chain)| match response {
+            Ok(value) => parse_quantity(value),
+            Err(e) => {
+                warn!("{} 获取最新区块失败: {}", chain.name, e);
+                None
+            }
+        })
+        .max()
+}
+
+async fn close_all(managers: Vec<RpcManager>) {
+    for mut manager in managers {
+        manager.close().await;
+    }
+}
+
+/// 规范化结果：十六进制字符串转为小写，区块和日志只保留需要比较的字段，日志按 logIndex 排序
+fn normalize(method: &str, value: &Value) -> Value {
+    let value = lowercase_hex(value);
+    match method {
+        "eth_getBlockByNumber" => pick_fields(&value, &BLOCK_FIELDS),
+        "eth_getLogs" => match value.as_array() {
+            Some(logs) => Value::Array(
+                logs.iter()
+                    .map(|log| pick_fields(log, &LOG_FIELDS))
+                    .sorted_by_key(|log| log.get("logIndex").and_then(parse_quantity))
+                    .collect(),
+            ),
+            None => value,
+        },
+        _ => value,
+    }
+}
+
+fn lowercase_hex(value: &Value) -> Value {
+    match value {
+        Value::String(s) if s.starts_with("0x") || s.starts_with("0X") => Value::String(s.to_lowercase()),
+        Value::Array(items) => Value::Array(items.iter().map(lowercase_hex).collect()),
+        Value::Object(object) => Value::Object(object.iter().map(|(k, v)| (k.clone(), lowercase_hex(v))).collect()),
+        other => other.clone(),
+    }
+}
+
+fn pick_fields(value: &Value, fields: &[&str]) -> Value {
+    match value.as_object() {
+        Some(object) => Value::Object(
+            fields
+                .iter()
+                .filter_map(|&name| object.get(name).map(|v| (name.to_string(), v.clone())))
+                .collect::<Map<_, _>>(),
+        ),
+        None => value.clone(),
+    }
+}
+
+/// 将各端点的结果与多数结果比较
+///
+/// 多数结果是成功的端点中出现次数唯一最多的结果。出现次数最多的结果不止一个时（例如两个端点各不相同）
+/// 没有多数结果，所有成功的端点都记为 [`Agreement::NoMajority`]，并列出与其他每种结果的差异。
+fn compare(
+    network: &str,
+    block: u64,
+    method: &str,
+    group: &[&Chain],
+    values: &[Result<Value, String>],
+) -> Vec<ConsistencyResult> {
+    let mut counts: Vec<(&Value, usize)> = Vec::new();
+    for value in values.iter().flatten() {
+        match counts.iter_mut().find(|(v, _)| *v == value) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((value, 1)),
+        }
+    }
+    let majority = counts.iter().max_by_key(|(_, count)| *count).copied();
+    let tied = majority.is_some_and(|(_, top)| counts.iter().filter(|(_, count)| *count == top).count() > 1);
+
+    // 返回每种结果的端点名称
+    let holders = |reference: &Value| {
+        group
+            .iter()
+            .zip(values)
+            .filter(|(_, value)| value.as_ref().ok() == Some(reference))
+            .map(|(chain, _)| chain.name.as_str())
+            .join(", ")
+    };
+
+    group
+        .iter()
+        .zip(values)
+        .map(|(chain, value)| {
+            let (agreement, detail) = match (value, majority) {
+                (Err(e), _) => (Agreement::Error, e.clone()),
+                (Ok(value), Some(_)) if tied => {
+                    let detail = counts
+                        .iter()
+                        .filter(|(reference, _)| *reference != value)
+                        .map(|(reference, _)| format!("与 {}: {}", holders(reference), diff_summary(reference, value)))
+                        .join(" | ");
+                    (Agreement::NoMajority, format!("没有多数结果; {}", detail))
+                }
+                (Ok(value), Some((reference, _))) if value == reference => (Agreement::Agree, String::new()),
+                (Ok(value), Some((reference, _))) => (Agreement::Disagree, diff_summary(reference, value)),
+                (Ok(_), None) => unreachable!("成功的结果至少计数一次"),
+            };
+            ConsistencyResult {
+                network: network.to_string(),
+                block,
+                method: method.to_string(),
+                chain: chain.name.clone(),
+                endpoint: chain.rpc_url.clone(),
+                agreement,
+                majority_count: majority.map_or(0, |(_, count)| count),
+                endpoint_count: group.len(),
+                detail,
+            }
+        })
+        .collect()
+}
+
+/// 列出结果与多数结果的差异，格式为 `路径: 多数结果 → 该端点的结果`
+fn diff_summary(reference: &Value, value: &Value) -> String {
+    let mut diffs = Vec::new();
+    diff(reference, value, "result", &mut diffs);
+    let total = diffs.len();
+    let mut summary = diffs.into_iter().take(MAX_DIFFS).join("; ");
+    if total > MAX_DIFFS {
+        summary.push_str(&format!("; 另有 {} 处不同", total - MAX_DIFFS));
+    }
+    summary
+}
+
+fn diff(reference: &Value, value: &Value, path: &str, diffs: &mut Vec<String>) {
+    match (reference, value) {
+        (Value::Object(a), Value::Object(b)) => {
+            for key in a.keys().chain(b.keys()).unique().sorted() {
+                let path = format!("{}.{}", path, key);
+                diff(a.get(key).unwrap_or(&Value::Null), b.get(key).unwrap_or(&Value::Null), &path, diffs);
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                diff(x, y, &format!("{}[{}]", path, i), diffs);
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            diffs.push(format!("{}: {} 项 → {} 项", path, a.len(), b.len()));
+        }
+        _ if reference != value => {
+            diffs.push(format!("{}: {} → {}", path, excerpt(reference), excerpt(value)));
+        }
+        _ => {}
+    }
+}
+
+/// 将一致性检查结果写入 CSV 文件
+pub fn write_to_csv(results: &[ConsistencyResult], output_path: &Path) -> Result<()> {
+    let file = File::create(output_path)?;
+    let mut wtr = csv::Writer::from_writer(file);
+
+    wtr.write_record([
+        "network",
+        "block",
+        "method",
+        "chain",
+        "endpoint",
+        "status",
+        "majority_count",
+        "endpoint_count",
+        "detail",
+    ])?;
+
+    for result in results {
+        wtr.write_record([
+            &result.network,
+            &result.block.to_string(),
+            &result.method,
+            &result.chain,
+            &result.endpoint,
+            result.agreement.name(),
+            &result.majority_count.to_string(),
+            &result.endpoint_count.to_string(),
+            &result.detail,
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// 在控制台中打印每个网络、每个查询的一致情况，以及不一致和失败的端点
+pub fn print_stats(results: &[ConsistencyResult]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+
+    table.add_row(Row::new(vec![
+        Cell::new("网络"),
+        Cell::new("区块"),
+        Cell::new("方法"),
+        Cell::new("一致"),
+        Cell::new("不一致"),
+        Cell::new("无多数"),
+        Cell::new("失败"),
+    ]));
+
+    for ((network, method), group) in &results.iter().group_by(|r| (r.network.as_str(), r.method.as_str())) {
+        let group: Vec<_> = group.collect();
+        let names = |agreement: Agreement| {
+            group.iter().filter(|r| r.agreement == agreement).map(|r| r.chain.as_str()).join(", ")
+        };
+        let agreeing = group.iter().filter(|r| r.agreement == Agreement::Agree).count();
+        let color = if agreeing == group.len() { "Fg=Green" } else { "Fg=Red" };
+
+        table.add_row(Row::new(vec![
+            Cell::new(network),
+            Cell::new(&group[0].block.to_string()),
+            Cell::new(method),
+            Cell::new(&format!("{}/{}", agreeing, group.len())).style_spec(color),
+            Cell::new(&names(Agreement::Disagree)),
+            Cell::new(&names(Agreement::NoMajority)),
+            Cell::new(&names(Agreement::Error)),
+        ]));
+    }
+
+    table.printstd();
+
+    let mismatches: Vec<_> = results.iter().filter(|r| r.agreement != Agreement::Agree).collect();
+    if mismatches.is_empty() {
+        println!("所有端点的结果一致");
+        return;
+    }
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("方法"),
+        Cell::new("状态"),
+        Cell::new("差异 (多数结果 → 该端点)"),
+    ]));
+    for result in mismatches {
+        table.add_row(Row::new(vec![
+            Cell::new(&result.chain),
+            Cell::new(&result.method),
+            Cell::new(result.agreement.name()).style_spec("Fg=Red"),
+            Cell::new(&result.detail),
+        ]));
+    }
+    table.printstd();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chains(count: usize) -> Vec<Chain> {
+        (1..=count)
+            .map(|i| Chain::new_http(&format!("ETH-HTTP-{}", i), &format!("http://node{}:8545", i)))
+            .collect()
+    }
+
+    #[test]
+    fn hex_case_is_ignored() {
+        assert_eq!(normalize("eth_getBalance", &json!("0xABCdef")), normalize("eth_getBalance", &json!("0xabcDEF")));
+        let block = |hash: &str| json!({ "number": "0x1", "hash": hash, "size": "0x200" });
+        assert_eq!(
+            normalize("eth_getBlockByNumber", &block("0xAA")),
+            normalize("eth_getBlockByNumber", &block("0xaa"))
+        );
+    }
+
+    #[test]
+    fn log_fields_outside_log_fields_are_ignored_and_logs_sorted() {
+        let a = json!([
+            { "logIndex": "0x2", "data": "0x02", "removed": false },
+            { "logIndex": "0x1", "data": "0x01" }
+        ]);
+        let b = json!([
+            { "logIndex": "0x1", "data": "0x01", "blockTimestamp": "0x65" },
+            { "logIndex": "0x2", "data": "0x02" }
+        ]);
+        assert_eq!(normalize("eth_getLogs", &a), normalize("eth_getLogs", &b));
+    }
+
+    #[test]
+    fn field_mismatch_is_reported_with_path() {
+        let reference = json!([{ "logIndex": "0x1", "topics": ["0xaa", "0xbb"] }]);
+        let value = json!([{ "logIndex": "0x1", "topics": ["0xaa", "0xcc"] }]);
+        assert_eq!(diff_summary(&reference, &value), r#"result[0].topics[1]: "0xbb" → "0xcc""#);
+        assert_eq!(diff_summary(&json!([1, 2]), &json!([1])), "result: 2 项 → 1 项");
+    }
+
+    #[test]
+    fn majority_wins_two_to_one() {
+        let chains = chains(3);
+        let group: Vec<&Chain> = chains.iter().collect();
+        // 第一个端点是少数，多数结果仍取其余两个端点的结果
+        let values = vec![Ok(json!("0x11")), Ok(json!("0x10")), Ok(json!("0x10"))];
+        let results = compare("ETH", 100, "eth_getBalance", &group, &values);
+        let agreements: Vec<_> = results.iter().map(|r| r.agreement).collect();
+        assert_eq!(agreements, vec![Agreement::Disagree, Agreement::Agree, Agreement::Agree]);
+        assert!(results.iter().all(|r| r.majority_count == 2 && r.endpoint_count == 3));
+        assert_eq!(results[0].detail, r#"result: "0x10" → "0x11""#);
+    }
+
+    #[test]
+    fn errors_do_not_count_towards_majority() {
+        let chains = chains(3);
+        let group: Vec<&Chain> = chains.iter().collect();
+        let values = vec![Err("timeout".to_string()), Ok(json!("0x10")), Ok(json!("0x11"))];
+        let results = compare("ETH", 100, "eth_getBalance", &group, &values);
+        assert_eq!(results[0].agreement, Agreement::Error);
+        // 1 比 1 没有多数，两个成功的端点都不判为一致或不一致
+        assert_eq!(results[1].agreement, Agreement::NoMajority);
+        assert_eq!(results[2].agreement, Agreement::NoMajority);
+        assert_eq!(results[1].detail, r#"没有多数结果; 与 ETH-HTTP-3: result: "0x11" → "0x10""#);
+        assert_eq!(results[2].detail, r#"没有多数结果; 与 ETH-HTTP-2: result: "0x10" → "0x11""#);
+    }
+
+    #[test]
+    fn tie_lists_diff_against_each_alternative() {
+        let chains = chains(4);
+        let group: Vec<&Chain> = chains.iter().collect();
+        let values = vec![Ok(json!("0x1")), Ok(json!("0x1")), Ok(json!("0x2")), Ok(json!("0x3"))];
+        let results = compare("ETH", 100, "eth_getBalance", &group, &values);
+        // 2 比 1 比 1 仍有唯一的多数结果
+        let agreements: Vec<_> = results.iter().map(|r| r.agreement).collect();
+        assert_eq!(
+            agreements,
+            vec![Agreement::Agree, Agreement::Agree, Agreement::Disagree, Agreement::Disagree]
+        );
+
+        let values = vec![Ok(json!("0x1")), Ok(json!("0x2")), Ok(json!("0x3"))];
+        let results = compare("ETH", 100, "eth_getBalance", &group[..3], &values);
+        assert!(results.iter().all(|r| r.agreement == Agreement::NoMajority));
+        assert_eq!(
+            results[0].detail,
+            r#"没有多数结果; 与 ETH-HTTP-2: result: "0x2" → "0x1" | 与 ETH-HTTP-3: result: "0x3" → "0x1""#
+        );
+    }
+
+    #[test]
+    fn queries_use_network_specific_targets() {
+        let targets = |network: &str| {
+            queries(network, 1)
+                .iter()
+                .find(|m| m.name == "eth_getCode")
+                .map(|m| m.params[0].clone())
+                .unwrap()
+        };
+        assert_eq!(targets("ETH"), json!(DAI_CONTRACT));
+        assert_eq!(targets("BSC"), json!(WBNB_CONTRACT));
+    }
+}
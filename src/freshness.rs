@@ -0,0 +1,325 @@
+use crate::chains::{Chain, ConnectionType};
+use crate::methods::RpcMethod;
+use crate::rpc::{Config, RpcManager};
+use crate::validate::parse_quantity;
+use anyhow::Result;
+use futures::future;
+use itertools::Itertools;
+use log::{info, warn};
+use prettytable::{format, Cell, Row, Table};
+use serde_json::{json, Value};
+use std::fs::File;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 区块新鲜度采样的配置
+#[derive(Debug, Clone)]
+pub struct FreshnessConfig {
+    /// 采样轮数
+    pub samples: usize,
+    /// 两轮采样之间的间隔
+    pub interval: Duration,
+}
+
+/// 一组落后程度样本的汇总
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LagSummary {
+    pub avg: f64,
+    pub median: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl LagSummary {
+    /// 汇总样本，没有样本时返回全零
+    fn from_samples(samples: &mut [f64]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_by(|a, b| a.total_cmp(b));
+        // 最近秩法：第 q 分位数是排序后第 ceil(q * n) 个样本
+        let quantile = |q: f64| samples[((q * samples.len() as f64).ceil() as usize).clamp(1, samples.len()) - 1];
+        Self {
+            avg: samples.iter().sum::<f64>() / samples.len() as f64,
+            median: quantile(0.5),
+            p95: quantile(0.95),
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+/// 一个端点的区块新鲜度统计
+#[derive(Debug, Clone)]
+pub struct FreshnessStats {
+    pub chain: String,
+    pub endpoint: String,
+    /// 采样次数
+    pub sample_count: usize,
+    /// 失败或返回无效区块的采样次数
+    pub error_count: usize,
+    /// 落后于同一轮同网络最高区块的区块数
+    pub block_lag: LagSummary,
+    /// 收到响应时的本机时间与最新区块时间戳之差（秒）
+    pub time_lag: LagSummary,
+    /// 落后于最高区块的样本比例
+    pub behind_rate: f64,
+}
+
+/// 一次采样的结果：区块号、区块时间戳和收到响应的 Unix 时间（秒）
+type Sample = Option<(u64, u64, f64)>;
+
+/// 在所有 JSON-RPC 端点上同时采样最新区块，计算各端点落后于同网络最高区块和本机时间的程度
+///
+/// 每轮同时向所有端点发送 `eth_getBlockByNumber("latest")`，从同一个响应中取得区块号和时间戳。
+/// 区块落后数与同一轮、同一网络（例如 `ETH-HTTP` 和 `ETH-WS-2`）中最高的区块号比较；
+/// 时间落后包括出块间隔，以太坊上通常在 0-12 秒之间，并受本机时钟误差影响。
+pub async fn measure_freshness(
+    chains: &[Chain],
+    freshness: &FreshnessConfig,
+    config: &Config,
+) -> Result<Vec<FreshnessStats>> {
+    let chains: Vec<&Chain> = chains
+        .iter()
+        .filter(|c| matches!(c.connection_type, ConnectionType::Http | ConnectionType::WebSocket))
+        .collect();
+    let mut managers = Vec::new();
+    for chain in &chains {
+        managers.push(RpcManager::new(config.clone(), std::slice::from_ref(*chain))?);
+    }
+
+    println!(
+        "区块新鲜度采样: {} 个端点, {} 轮, 间隔 {} 毫秒",
+        chains.len(),
+        freshness.samples,
+        freshness.interval.as_millis()
+    );
+    info!("开始区块新鲜度采样");
+
+    let method = RpcMethod::new("eth_getBlockByNumber", vec![json!("latest"), json!(false)], "获取最新区块");
+    let mut block_lags: Vec<Vec<f64>> = vec![Vec::new(); chains.len()];
+    let mut time_lags: Vec<Vec<f64>> = vec![Vec::new(); chains.len()];
+    let mut sample_counts = vec![0usize; chains.len()];
+    let mut error_counts = vec![0usize; chains.len()];
+    let mut behind_counts = vec![0usize; chains.len()];
+
+    let mut ticker = tokio::time::interval(freshness.interval);
+    for _ in 0..freshness.samples {
+        ticker.tick().await;
+        if config.remaining().is_some_and(|remaining| remaining.is_zero()) {
+            warn!("已到达测试截止时间，停止区块新鲜度采样");
+            break;
+        }
+
+        // 请求不超过距截止时间的剩余时间，被截止时间打断的一轮不完整，不计入统计
+        let samples: Vec<Option<Sample>> = future::join_all(managers.iter_mut().zip(&chains).map(|(manager, chain)| {
+            let method = &method;
+            async move {
+                let response = match config.remaining() {
+                    Some(remaining) => tokio::time::timeout(remaining, manager.call(chain, method)).await.ok()?,
+                    None => manager.call(chain, method).await,
+                };
+                let received_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64();
+                Some(match response {
+                    Ok(block) => parse_block(&block).map(|(number, timestamp)| (number, timestamp, received_at)),
+                    Err(e) => {
+                        warn!("{} 区块新鲜度采样失败: {}", chain.name, e);
+                        None
+                    }
+                })
+            }
+        }))
+        .await;
+        let Some(samples) = samples.into_iter().collect::<Option<Vec<Sample>>>() else {
+            warn!("已到达测试截止时间，停止区块新鲜度采样");
+            break;
+        };
+
+        let numbers: Vec<(&str, Option<u64>)> = chains
+            .iter()
+            .zip(&samples)
+            .map(|(chain, sample)| (chain.network(), sample.map(|(number, _, _)| number)))
+            .collect();
+        for (i, (sample, lag)) in samples.iter().zip(block_lags_in_round(&numbers)).enumerate() {
+            sample_counts[i] += 1;
+            let (Some((_, timestamp, received_at)), Some(lag)) = (*sample, lag) else {
+                error_counts[i] += 1;
+                continue;
+            };
+            if lag > 0 {
+                behind_counts[i] += 1;
+            }
+            block_lags[i].push(lag as f64);
+            time_lags[i].push(received_at - timestamp as f64);
+        }
+    }
+
+    for mut manager in managers {
+        manager.close().await;
+    }
+
+    Ok(chains
+        .iter()
+        .enumerate()
+        .map(|(i, chain)| FreshnessStats {
+            chain: chain.name.clone(),
+            endpoint: chain.rpc_url.clone(),
+            sample_count: sample_counts[i],
+            error_count: error_counts[i],
+            block_lag: LagSummary::from_samples(&mut block_lags[i]),
+            time_lag: LagSummary::from_samples(&mut time_lags[i]),
+            behind_rate: match block_lags[i].len() {
+                0 => 0.0,
+                n => behind_counts[i] as f64 / n as f64,
+            },
+        })
+        .collect())
+}
+
+/// 计算一轮采样中各端点落后于同一网络最高区块号的区块数，采样失败的端点为 None
+fn block_lags_in_round(numbers: &[(&str, Option<u64>)]) -> Vec<Option<u64>> {
+    let heads = numbers
+        .iter()
+        .filter_map(|&(network, number)| number.map(|number| (network, number)))
+        .into_grouping_map()
+        .max();
+    numbers
+        .iter()
+        .map(|&(network, number)| number.map(|number| heads[network] - number))
+        .collect()
+}
+
+/// 从区块对象中取出区块号和时间戳
+fn parse_block(block: &Value) -> Option<(u64, u64)> {
+    Some((parse_quantity(block.get("number")?)?, parse_quantity(block.get("timestamp")?)?))
+}
+
+/// 将区块新鲜度统计写入 CSV 文件
+pub fn write_to_csv(stats: &[FreshnessStats], output_path: &Path) -> Result<()> {
+    let file = File::create(output_path)?;
+    let mut wtr = csv::Writer::from_writer(file);
+
+    wtr.write_record([
+        "chain",
+        "endpoint",
+        "sample_count",
+        "error_count",
+        "behind_percent",
+        "avg_block_lag",
+        "median_block_lag",
+        "p95_block_lag",
+        "max_block_lag",
+        "avg_time_lag_s",
+        "median_time_lag_s",
+        "p95_time_lag_s",
+        "max_time_lag_s",
+    ])?;
+
+    for stat in stats {
+        wtr.write_record([
+            &stat.chain,
+            &stat.endpoint,
+            &stat.sample_count.to_string(),
+            &stat.error_count.to_string(),
+            &format!("{:.2}", stat.behind_rate * 100.0),
+            &format!("{:.2}", stat.block_lag.avg),
+            &format!("{:.0}", stat.block_lag.median),
+            &format!("{:.0}", stat.block_lag.p95),
+            &format!("{:.0}", stat.block_lag.max),
+            &format!("{:.2}", stat.time_lag.avg),
+            &format!("{:.2}", stat.time_lag.median),
+            &format!("{:.2}", stat.time_lag.p95),
+            &format!("{:.2}", stat.time_lag.max),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// 在控制台中打印区块新鲜度统计
+pub fn print_stats(stats: &[FreshnessStats]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("采样"),
+        Cell::new("失败"),
+        Cell::new("落后比例"),
+        Cell::new("平均落后区块"),
+        Cell::new("P95落后区块"),
+        Cell::new("最大落后区块"),
+        Cell::new("平均时间落后(s)"),
+        Cell::new("P95时间落后(s)"),
+        Cell::new("最大时间落后(s)"),
+    ]));
+
+    for stat in stats {
+        let lag_color = if stat.block_lag.max == 0.0 {
+            "Fg=Green"
+        } else if stat.block_lag.p95 <= 1.0 {
+            "Fg=Yellow"
+        } else {
+            "Fg=Red"
+        };
+
+        table.add_row(Row::new(vec![
+            Cell::new(&stat.chain),
+            Cell::new(&stat.sample_count.to_string()),
+            Cell::new(&stat.error_count.to_string()),
+            Cell::new(&format!("{:.2}%", stat.behind_rate * 100.0)).style_spec(lag_color),
+            Cell::new(&format!("{:.2}", stat.block_lag.avg)),
+            Cell::new(&format!("{:.0}", stat.block_lag.p95)).style_spec(lag_color),
+            Cell::new(&format!("{:.0}", stat.block_lag.max)),
+            Cell::new(&format!("{:.2}", stat.time_lag.avg)),
+            Cell::new(&format!("{:.2}", stat.time_lag.p95)),
+            Cell::new(&format!("{:.2}", stat.time_lag.max)),
+        ]));
+    }
+
+    table.printstd();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lag_summary_uses_nearest_rank() {
+        let mut samples: Vec<f64> = (1..=20).rev().map(f64::from).collect();
+        let summary = LagSummary::from_samples(&mut samples);
+        assert_eq!(summary.avg, 10.5);
+        assert_eq!(summary.median, 10.0);
+        assert_eq!(summary.p95, 19.0);
+        assert_eq!(summary.max, 20.0);
+
+        let single = LagSummary::from_samples(&mut [3.0]);
+        assert_eq!((single.median, single.p95, single.max), (3.0, 3.0, 3.0));
+
+        let empty = LagSummary::from_samples(&mut []);
+        assert_eq!((empty.avg, empty.max), (0.0, 0.0));
+    }
+
+    #[test]
+    fn block_lag_is_relative_to_same_network_head() {
+        let numbers = [
+            ("ETH", Some(100)),
+            ("ETH", Some(98)),
+            ("BSC", Some(5000)),
+            ("ETH", None),
+            ("BSC", Some(4999)),
+        ];
+        assert_eq!(
+            block_lags_in_round(&numbers),
+            [Some(0), Some(2), Some(0), None, Some(1)]
+        );
+        assert_eq!(block_lags_in_round(&[("ETH", None)]), [None]);
+    }
+
+    #[test]
+    fn block_needs_number_and_timestamp() {
+        assert_eq!(parse_block(&json!({ "number": "0x10", "timestamp": "0x5" })), Some((16, 5)));
+        assert_eq!(parse_block(&json!({ "number": "0x10" })), None);
+        assert_eq!(parse_block(&Value::Null), None);
+    }
+}
@@ -0,0 +1,542 @@
+use crate::chains::{Chain, ConnectionType};
+use crate::methods::{self, RpcMethod};
+use crate::rpc::{self, Config, RpcManager, RpcResult};
+use anyhow::Result;
+use futures::future;
+use futures::stream::{FuturesUnordered, StreamExt};
+use hdrhistogram::Histogram;
+use log::{info, warn};
+use prettytable::{format, Cell, Row, Table};
+use std::fs::File;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+/// 汇总行使用的方法名称
+const ALL_METHODS: &str = "*";
+
+/// 负载测试参数
+#[derive(Debug, Clone)]
+pub struct LoadConfig {
+    /// 每个端点的目标请求速率（请求/秒）
+    pub rate: f64,
+    /// 测试时长
+    pub duration: Duration,
+    /// 每个端点同时在途的请求数上限
+    pub max_in_flight: usize,
+}
+
+/// 一个端点上某个方法的负载测试统计，`method` 为 `*` 时是该端点所有方法的汇总
+///
+/// 并发阶段没有目标速率，`target_rps` 为 0。
+#[derive(Debug, Clone)]
+pub struct LoadStats {
+    pub chain: String,
+    pub endpoint: String,
+    pub method: String,
+    /// 目标速率（请求/秒）
+    pub target_rps: f64,
+    /// 按计划应发送的请求数
+    pub scheduled: u64,
+    /// 实际发送的请求数
+    pub sent: u64,
+    pub success_count: u64,
+    pub error_count: u64,
+    /// 因在途请求达到上限、测试结束时仍未发送的请求数
+    pub unsent: u64,
+    /// 实际完成速率（请求/秒）
+    pub achieved_rps: f64,
+    /// 从计划发送时间起算的延迟百分位（毫秒），已校正协调遗漏
+    pub latency: LatencyPercentiles,
+    /// 从实际发送时间起算的服务延迟百分位（毫秒），未校正
+    pub service_latency: LatencyPercentiles,
+    pub proxy: String,
+}
+
+impl LoadStats {
+    /// 错误率（占已完成请求的比例）
+    pub fn error_rate(&self) -> f64 {
+        let completed = self.success_count + self.error_count;
+        if completed == 0 {
+            0.0
+        } else {
+            self.error_count as f64 / completed as f64
+        }
+    }
+}
+
+/// 延迟百分位（毫秒）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LatencyPercentiles {
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub p999: f64,
+    pub max: f64,
+}
+
+impl LatencyPercentiles {
+    fn from_histogram(histogram: &Histogram<u64>) -> Self {
+        if histogram.is_empty() {
+            return Self::default();
+        }
+        let ms = |micros: u64| micros as f64 / 1000.0;
+        Self {
+            p50: ms(histogram.value_at_quantile(0.5)),
+            p90: ms(histogram.value_at_quantile(0.9)),
+            p99: ms(histogram.value_at_quantile(0.99)),
+            p999: ms(histogram.value_at_quantile(0.999)),
+            max: ms(histogram.max()),
+        }
+    }
+}
+
+/// 单个方法的负载测试记录，延迟以微秒记录
+struct Recorder {
+    corrected: Histogram<u64>,
+    service: Histogram<u64>,
+    sent: u64,
+    success_count: u64,
+    error_count: u64,
+    unsent: u64,
+}
+
+impl Recorder {
+    fn new() -> Self {
+        // 1 微秒到 1 小时，3 位有效数字
+        let histogram = || Histogram::new_with_bounds(1, 3_600_000_000, 3).expect("直方图参数有效");
+        Self {
+            corrected: histogram(),
+            service: histogram(),
+            sent: 0,
+            success_count: 0,
+            error_count: 0,
+            unsent: 0,
+        }
+    }
+
+    /// 记录一个已完成的请求，只有成功的请求计入延迟分布
+    fn record(&mut self, result: &RpcResult, intended: Instant, sent: Instant, completed: Instant) {
+        if result.success {
+            self.success_count += 1;
+            self.corrected.saturating_record(micros(completed - intended));
+            self.service.saturating_record(micros(completed - sent));
+        } else {
+            self.error_count += 1;
+        }
+    }
+
+    /// 记录一个到计划时间但直到阶段结束仍未发送的请求
+    ///
+    /// 校正延迟按阶段结束时已等待的时间 `end - intended` 计入，是该请求实际延迟的下限；
+    /// 过载时这些请求等待最久，不计入会低估尾部延迟。服务延迟不受影响。
+    fn record_unsent(&mut self, intended: Instant, end: Instant) {
+        self.unsent += 1;
+        self.corrected.saturating_record(micros(end.saturating_duration_since(intended)));
+    }
+
+    fn merge(&mut self, other: &Recorder) {
+        self.corrected.add(&other.corrected).expect("直方图范围相同");
+        self.service.add(&other.service).expect("直方图范围相同");
+        self.sent += other.sent;
+        self.success_count += other.success_count;
+        self.error_count += other.error_count;
+        self.unsent += other.unsent;
+    }
+}
+
+fn micros(duration: Duration) -> u64 {
+    duration.as_micros().min(u64::MAX as u128) as u64
+}
+
+/// 开环负载测试：按固定速率发出请求，不等待前一个请求完成
+///
+/// 每个端点按 `rate` 的目标速率轮流发送各方法的请求，各端点并行测试（受 `max_concurrent_requests` 限制）。
+/// 延迟从计划发送时间起算：在途请求达到上限或调度落后时，排队等待的时间同样计入延迟，
+/// 避免协调遗漏 (coordinated omission) 低估尾部延迟。负载测试不重试失败的请求。
+/// WebSocket 端点的请求在一条连接上依次发送，无法开环施压，因此跳过。
+pub async fn run_load_test(
+    chains: &[Chain],
+    methods: &[RpcMethod],
+    load: &LoadConfig,
+    config: &Config,
+) -> Result<Vec<LoadStats>> {
+    let http_chains: Vec<&Chain> = chains.iter().filter(|c| c.connection_type.is_http()).collect();
+    for chain in chains.iter().filter(|c| c.connection_type == ConnectionType::WebSocket) {
+        warn!("负载测试不支持WebSocket端点，跳过: {}", chain.name);
+    }
+
+    let managers = http_chains
+        .iter()
+        .map(|chain| RpcManager::new(config.clone(), std::slice::from_ref(*chain)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let engine_methods = methods::get_engine_methods();
+    let semaphore = Semaphore::new(config.max_concurrent_requests.max(1));
+
+    info!(
+        "开始负载测试: {} 个端点, 目标 {:.1} 请求/秒, {} 秒",
+        http_chains.len(),
+        load.rate,
+        load.duration.as_secs()
+    );
+
+    let runs = future::join_all(http_chains.iter().zip(&managers).map(|(chain, manager)| {
+        let chain_methods = rpc::methods_for_chain(chain, methods, &engine_methods);
+        let semaphore = &semaphore;
+        async move {
+            let _permit = semaphore.acquire().await.expect("信号量不会被关闭");
+            run_endpoint(manager, chain, &chain_methods, load, config).await
+        }
+    }))
+    .await;
+
+    Ok(runs.into_iter().flatten().collect())
+}
+
+/// 负载阶段：开环的固定请求速率，或闭环的固定并发数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    /// 每秒发出的请求数，不等待之前的请求完成
+    Rate(f64),
+    /// 同时工作的并发数，每个并发收到响应后立即发送下一个请求
+    Concurrency(usize),
+}
+
+impl Stage {
+    /// 显示名称
+    pub fn label(&self) -> String {
+        match self {
+            Stage::Rate(rate) => format!("{}/秒", rate),
+            Stage::Concurrency(workers) => format!("{}并发", workers),
+        }
+    }
+
+    /// 目标速率，并发阶段没有目标速率
+    pub fn target_rps(&self) -> Option<f64> {
+        match self {
+            Stage::Rate(rate) => Some(*rate),
+            Stage::Concurrency(_) => None,
+        }
+    }
+}
+
+/// 一个阶段结束时各方法的记录
+pub struct StageRun {
+    recorders: Vec<Recorder>,
+    elapsed: Duration,
+}
+
+/// 对一个端点施加负载，返回各方法及汇总统计
+async fn run_endpoint(
+    manager: &RpcManager,
+    chain: &Chain,
+    chain_methods: &[&RpcMethod],
+    load: &LoadConfig,
+    config: &Config,
+) -> Vec<LoadStats> {
+    let duration = stage_duration(load.duration, config);
+    if chain_methods.is_empty() || duration.is_zero() {
+        println!("[{}] 没有可测试的方法或已到达测试截止时间，跳过负载测试", chain.name);
+        return Vec::new();
+    }
+
+    println!(
+        "[{}] 负载测试: 目标 {:.1} 请求/秒, {:.0} 秒 - 端点: {}",
+        chain.name,
+        load.rate,
+        duration.as_secs_f64(),
+        chain.rpc_url
+    );
+
+    let stage = Stage::Rate(load.rate);
+    let run = run_stage(manager, chain, chain_methods, stage, duration, load.max_in_flight, config).await;
+    let stats = summarize(chain, chain_methods, stage, &run);
+
+    let summary = stats.last().unwrap();
+    println!(
+        "[{}] 负载测试完成: 实际 {:.1} 请求/秒, 错误率 {:.2}%, P99 {:.2}ms",
+        chain.name,
+        summary.achieved_rps,
+        summary.error_rate() * 100.0,
+        summary.latency.p99
+    );
+
+    stats
+}
+
+/// 阶段时长，不超过距测试截止时间的剩余时间
+pub fn stage_duration(duration: Duration, config: &Config) -> Duration {
+    match config.remaining() {
+        Some(remaining) => duration.min(remaining),
+        None => duration,
+    }
+}
+
+/// 在一个端点上执行一个负载阶段，`chain_methods` 不能为空
+pub async fn run_stage(
+    manager: &RpcManager,
+    chain: &Chain,
+    chain_methods: &[&RpcMethod],
+    stage: Stage,
+    duration: Duration,
+    max_in_flight: usize,
+    config: &Config,
+) -> StageRun {
+    match stage {
+        Stage::Rate(rate) => run_open_loop(manager, chain, chain_methods, rate, duration, max_in_flight, config).await,
+        Stage::Concurrency(workers) => run_closed_loop(manager, chain, chain_methods, workers, duration, config).await,
+    }
+}
+
+/// 按固定速率发送请求，直到阶段结束
+async fn run_open_loop(
+    manager: &RpcManager,
+    chain: &Chain,
+    chain_methods: &[&RpcMethod],
+    rate: f64,
+    duration: Duration,
+    max_in_flight: usize,
+    config: &Config,
+) -> StageRun {
+    let mut recorders: Vec<Recorder> = chain_methods.iter().map(|_| Recorder::new()).collect();
+    // 第 i 个请求的计划发送时间为 start + i / rate，方法按序号轮流
+    let scheduled = (duration.as_secs_f64() * rate).ceil() as u64;
+    let start = Instant::now();
+    let end = start + duration;
+    let intended_at = |i: u64| start + Duration::from_secs_f64(i as f64 / rate);
+    let mut next = 0u64;
+    let mut in_flight = FuturesUnordered::new();
+    let mut last_completion = start;
+    let mut fell_behind = false;
+
+    loop {
+        // 发出所有已到计划时间的请求，在途请求达到上限时排队；测试时长结束后不再发送
+        let now = Instant::now();
+        while now < end && next < scheduled && intended_at(next) <= now {
+            if in_flight.len() >= max_in_flight {
+                if !fell_behind {
+                    warn!("{} 在途请求达到上限 ({})，发送落后于计划", chain.name, max_in_flight);
+                    fell_behind = true;
+                }
+                break;
+            }
+            let index = next as usize % chain_methods.len();
+            let intended = intended_at(next);
+            next += 1;
+            recorders[index].sent += 1;
+            in_flight.push(async move {
+                let sent = Instant::now();
+                let result = manager.test_http_method(chain, chain_methods[index], config.compression).await;
+                (index, intended, sent, result)
+            });
+        }
+
+        let sending = next < scheduled && in_flight.len() < max_in_flight && Instant::now() < end;
+        if !sending && in_flight.is_empty() {
+            break;
+        }
+
+        tokio::select! {
+            Some((index, intended, sent, result)) = in_flight.next(), if !in_flight.is_empty() => {
+                let completed = Instant::now();
+                recorders[index].record(&result, intended, sent, completed);
+                last_completion = completed;
+            }
+            _ = tokio::time::sleep_until(intended_at(next).into()), if sending => {}
+        }
+    }
+
+    // 测试结束时仍在排队的请求，以阶段结束时已等待的时间计入校正延迟
+    for i in next..scheduled {
+        recorders[i as usize % chain_methods.len()].record_unsent(intended_at(i), end);
+    }
+
+    StageRun {
+        recorders,
+        elapsed: last_completion.max(end) - start,
+    }
+}
+
+/// 以固定并发数连续发送请求，每个并发收到响应后立即发送下一个，直到阶段结束
+///
+/// 闭环发送时不存在计划发送时间，校正延迟与服务延迟相同。
+async fn run_closed_loop(
+    manager: &RpcManager,
+    chain: &Chain,
+    chain_methods: &[&RpcMethod],
+    workers: usize,
+    duration: Duration,
+    config: &Config,
+) -> StageRun {
+    let start = Instant::now();
+    let end = start + duration;
+
+    let worker_runs = future::join_all((0..workers).map(|worker| async move {
+        let mut recorders: Vec<Recorder> = chain_methods.iter().map(|_| Recorder::new()).collect();
+        // 各并发错开起始方法，合起来仍按顺序轮流
+        let mut next = worker;
+        while Instant::now() < end {
+            let index = next % chain_methods.len();
+            next += workers;
+            recorders[index].sent += 1;
+            let sent = Instant::now();
+            let result = manager.test_http_method(chain, chain_methods[index], config.compression).await;
+            recorders[index].record(&result, sent, sent, Instant::now());
+        }
+        recorders
+    }))
+    .await;
+
+    let mut recorders: Vec<Recorder> = chain_methods.iter().map(|_| Recorder::new()).collect();
+    for worker_recorders in &worker_runs {
+        for (recorder, worker_recorder) in recorders.iter_mut().zip(worker_recorders) {
+            recorder.merge(worker_recorder);
+        }
+    }
+
+    StageRun {
+        recorders,
+        elapsed: start.elapsed().max(duration),
+    }
+}
+
+/// 汇总一个阶段的结果：每个方法一行，最后一行是 `*` 汇总
+pub fn summarize(chain: &Chain, chain_methods: &[&RpcMethod], stage: Stage, run: &StageRun) -> Vec<LoadStats> {
+    let target_rps = stage.target_rps().unwrap_or(0.0);
+    let method_rate = target_rps / chain_methods.len() as f64;
+    let mut total = Recorder::new();
+    let mut stats = Vec::with_capacity(chain_methods.len() + 1);
+    for (method, recorder) in chain_methods.iter().zip(&run.recorders) {
+        total.merge(recorder);
+        stats.push(to_stats(chain, &method.name, method_rate, recorder, run.elapsed));
+    }
+    stats.push(to_stats(chain, ALL_METHODS, target_rps, &total, run.elapsed));
+    stats
+}
+
+fn to_stats(chain: &Chain, method: &str, target_rps: f64, recorder: &Recorder, elapsed: Duration) -> LoadStats {
+    let completed = recorder.success_count + recorder.error_count;
+    LoadStats {
+        chain: chain.name.clone(),
+        endpoint: chain.rpc_url.clone(),
+        method: method.to_string(),
+        target_rps,
+        scheduled: recorder.sent + recorder.unsent,
+        sent: recorder.sent,
+        success_count: recorder.success_count,
+        error_count: recorder.error_count,
+        unsent: recorder.unsent,
+        achieved_rps: completed as f64 / elapsed.as_secs_f64(),
+        latency: LatencyPercentiles::from_histogram(&recorder.corrected),
+        service_latency: LatencyPercentiles::from_histogram(&recorder.service),
+        proxy: chain.proxy_label(),
+    }
+}
+
+/// 将负载测试统计写入 CSV 文件
+pub fn write_to_csv(stats: &[LoadStats], output_path: &Path) -> Result<()> {
+    let file = File::create(output_path)?;
+    let mut wtr = csv::Writer::from_writer(file);
+
+    wtr.write_record([
+        "chain",
+        "endpoint",
+        "method",
+        "target_rps",
+        "achieved_rps",
+        "scheduled",
+        "sent",
+        "success_count",
+        "error_count",
+        "unsent",
+        "error_percent",
+        "p50_latency_ms",
+        "p90_latency_ms",
+        "p99_latency_ms",
+        "p999_latency_ms",
+        "max_latency_ms",
+        "service_p50_latency_ms",
+        "service_p99_latency_ms",
+        "proxy",
+    ])?;
+
+    for stat in stats {
+        wtr.write_record([
+            &stat.chain,
+            &stat.endpoint,
+            &stat.method,
+            &format!("{:.2}", stat.target_rps),
+            &format!("{:.2}", stat.achieved_rps),
+            &stat.scheduled.to_string(),
+            &stat.sent.to_string(),
+            &stat.success_count.to_string(),
+            &stat.error_count.to_string(),
+            &stat.unsent.to_string(),
+            &format!("{:.2}", stat.error_rate() * 100.0),
+            &format!("{:.2}", stat.latency.p50),
+            &format!("{:.2}", stat.latency.p90),
+            &format!("{:.2}", stat.latency.p99),
+            &format!("{:.2}", stat.latency.p999),
+            &format!("{:.2}", stat.latency.max),
+            &format!("{:.2}", stat.service_latency.p50),
+            &format!("{:.2}", stat.service_latency.p99),
+            &stat.proxy,
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// 在控制台中打印负载测试统计
+pub fn print_stats(stats: &[LoadStats]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("方法"),
+        Cell::new("目标/秒"),
+        Cell::new("实际/秒"),
+        Cell::new("成功"),
+        Cell::new("错误"),
+        Cell::new("错误率"),
+        Cell::new("未发送"),
+        Cell::new("P50(ms)"),
+        Cell::new("P90(ms)"),
+        Cell::new("P99(ms)"),
+        Cell::new("P99.9(ms)"),
+        Cell::new("最大(ms)"),
+        Cell::new("服务P99(ms)"),
+    ]));
+
+    for stat in stats {
+        let error_color = if stat.error_rate() == 0.0 {
+            "Fg=Green"
+        } else if stat.error_rate() < 0.01 {
+            "Fg=Yellow"
+        } else {
+            "Fg=Red"
+        };
+        let method = if stat.method == ALL_METHODS { "(全部)" } else { &stat.method };
+
+        table.add_row(Row::new(vec![
+            Cell::new(&stat.chain),
+            Cell::new(method),
+            Cell::new(&format!("{:.1}", stat.target_rps)),
+            Cell::new(&format!("{:.1}", stat.achieved_rps)),
+            Cell::new(&stat.success_count.to_string()),
+            Cell::new(&stat.error_count.to_string()),
+            Cell::new(&format!("{:.2}%", stat.error_rate() * 100.0)).style_spec(error_color),
+            Cell::new(&stat.unsent.to_string()),
+            Cell::new(&format!("{:.2}", stat.latency.p50)),
+            Cell::new(&format!("{:.2}", stat.latency.p90)),
+            Cell::new(&format!("{:.2}", stat.latency.p99)),
+            Cell::new(&format!("{:.2}", stat.latency.p999)),
+            Cell::new(&format!("{:.2}", stat.latency.max)),
+            Cell::new(&format!("{:.2}", stat.service_latency.p99)),
+        ]));
+    }
+
+    table.printstd();
+}
@@ -1,7 +1,23 @@
+mod assertion;
+mod auth;
 mod chains;
+mod compression;
+mod config;
+mod consistency;
+mod freshness;
+mod load;
 mod methods;
+mod net;
+mod pacing;
+mod probe;
+mod ramp;
+mod ratelimit;
+mod retry;
 mod rpc;
 mod stats;
+mod subscriptions;
+mod tls;
+mod validate;
 
 use anyhow::Result;
 use clap::Parser;
@@ -16,26 +32,59 @@ use url::Url;
     version
 )]
 struct Cli {
-    /// 以太坊 RPC URL (HTTP/HTTPS)
+    /// 以太坊 RPC URL (HTTP/HTTPS)，可重复指定以比较多个端点
     #[clap(short = 'e', long, default_value = "https://ethereum.publicnode.com")]
-    eth_rpc: String,
+    eth_rpc: Vec<String>,
 
-    /// BSC RPC URL (HTTP/HTTPS)
+    /// BSC RPC URL (HTTP/HTTPS)，可重复指定以比较多个端点
     #[clap(short = 'b', long, default_value = "https://bsc-dataseed1.binance.org")]
-    bsc_rpc: String,
+    bsc_rpc: Vec<String>,
     
-    /// 以太坊 WebSocket URL (WS/WSS)
+    /// 以太坊 WebSocket URL (WS/WSS)，可重复指定
     #[clap(long)]
-    eth_ws: Option<String>,
+    eth_ws: Vec<String>,
 
-    /// BSC WebSocket URL (WS/WSS)
+    /// BSC WebSocket URL (WS/WSS)，可重复指定
     #[clap(long)]
-    bsc_ws: Option<String>,
+    bsc_ws: Vec<String>,
 
-    /// 每个方法测试次数 (1-100)
+    /// 以太坊 GraphQL (EIP-1767) URL (HTTP/HTTPS)，可重复指定
+    #[clap(long)]
+    eth_graphql: Vec<String>,
+
+    /// BSC GraphQL (EIP-1767) URL (HTTP/HTTPS)，可重复指定
+    #[clap(long)]
+    bsc_graphql: Vec<String>,
+
+    /// 执行客户端 Engine API URL (HTTP/HTTPS，通常为 8551 端口)，可重复指定，测试 Engine 方法组
+    #[clap(long)]
+    engine_rpc: Vec<String>,
+
+    /// Engine API 的 JWT 密钥文件 (十六进制)，格式为 路径 或 端点名称=路径
+    #[clap(long)]
+    jwt_secret: Vec<String>,
+
+    /// 每个方法测试次数
     #[clap(short = 'c', long, default_value = "10")]
     count: usize,
 
+    /// 耐久测试时长 (例如 3600、90m、24h)，在该时长内轮流测试所有方法，代替 --count
+    #[clap(long, conflicts_with = "count")]
+    duration: Option<String>,
+
+    /// 正式测试前每个方法的预热请求次数，预热请求检查错误但不计入延迟统计 [默认: 0]
+    #[clap(long)]
+    warmup: Option<usize>,
+
+    /// 请求调度方式: endpoint (各端点独立并行测试), round-robin (每轮交错测试所有端点),
+    /// random (每轮随机顺序), method (逐个方法交错测试所有端点)
+    #[clap(long, default_value = "endpoint")]
+    schedule: String,
+
+    /// 随机调度的种子，不指定时随机生成并打印，用于复现请求顺序
+    #[clap(long)]
+    schedule_seed: Option<u64>,
+
     /// CSV 输出文件路径
     #[clap(short = 'o', long, default_value = "rpc-metrics.csv")]
     output: PathBuf,
@@ -52,6 +101,196 @@ struct Cli {
     #[clap(long, conflicts_with = "methods")]
     extended: bool,
 
+    /// 连接复用模式: warm (复用连接), cold (每次新建连接), both (分别测量并对比)
+    #[clap(long, default_value = "warm")]
+    connection_mode: String,
+
+    /// 代理，格式为 URL (所有端点) 或 端点名称=URL (例如 ETH-WS-2=socks5h://127.0.0.1:1080)，可重复指定
+    ///
+    /// 支持 http://、socks5:// 和 socks5h:// 代理
+    #[clap(long)]
+    proxy: Vec<String>,
+
+    /// 额外信任的 CA 证书包 (PEM)，格式为 路径 或 端点名称=路径，可重复指定
+    #[clap(long)]
+    tls_ca: Vec<String>,
+
+    /// 双向 TLS 客户端证书 (PEM)，格式为 路径 或 端点名称=路径
+    #[clap(long)]
+    tls_client_cert: Vec<String>,
+
+    /// 双向 TLS 客户端私钥 (PKCS#8 PEM)，格式为 路径 或 端点名称=路径
+    #[clap(long)]
+    tls_client_key: Vec<String>,
+
+    /// 服务端证书的 SHA-256 指纹 (十六进制)，格式为 指纹 或 端点名称=指纹，可重复指定
+    #[clap(long)]
+    tls_pin: Vec<String>,
+
+    /// 跳过证书校验的端点名称，all 表示所有端点 (仅限实验环境)
+    #[clap(long)]
+    tls_insecure: Vec<String>,
+
+    /// HTTP 响应压缩协商方式: none, gzip, br, zstd, auto (同时接受三种编码)
+    #[clap(long, default_value = "none")]
+    compression: String,
+
+    /// 对每个 HTTP 端点分别以不压缩、gzip、br 和 zstd 发送请求并对比
+    #[clap(long)]
+    compare_compression: bool,
+
+    /// 被限流时按 Retry-After 等待后再发送下一个请求 (单次最多等待60秒)
+    #[clap(long)]
+    honor_retry_after: bool,
+
+    /// 每次调用的最大尝试次数 (1-10)，1 表示不重试 [默认: 1]
+    #[clap(long)]
+    retry_attempts: Option<u32>,
+
+    /// 第一次重试前的基础退避时间（毫秒），之后每次翻倍并随机抖动 [默认: 100]
+    #[clap(long)]
+    retry_backoff_ms: Option<u64>,
+
+    /// 重试退避时间上限（毫秒）[默认: 2000]
+    #[clap(long)]
+    retry_max_backoff_ms: Option<u64>,
+
+    /// 可重试的错误类型，逗号分隔 [默认: network,timeout,websocket,http_status,rate_limited]
+    #[clap(long)]
+    retry_on: Option<String>,
+
+    /// WebSocket 断线后的最大重连次数
+    #[clap(long, default_value = "5")]
+    ws_reconnect_attempts: u32,
+
+    /// WebSocket 重连初始退避时间（毫秒），每次失败后翻倍
+    #[clap(long, default_value = "500")]
+    ws_reconnect_backoff_ms: u64,
+
+    /// WebSocket 重连退避时间上限（毫秒）
+    #[clap(long, default_value = "10000")]
+    ws_reconnect_max_backoff_ms: u64,
+
+    /// WebSocket 单条消息的最大字节数
+    #[clap(long, default_value = "67108864")]
+    ws_max_message_size: usize,
+
+    /// WebSocket 单个帧的最大字节数
+    #[clap(long, default_value = "16777216")]
+    ws_max_frame_size: usize,
+
+    /// 订阅吞吐量测试，逗号分隔 (newHeads, logs, newPendingTransactions)，仅对WebSocket端点生效
+    #[clap(long)]
+    subscribe: Option<String>,
+
+    /// 每种订阅的测试时长（秒）
+    #[clap(long, default_value = "60")]
+    subscribe_secs: u64,
+
+    /// logs 订阅使用的过滤条件 (JSON)
+    #[clap(long, default_value = "{}")]
+    logs_filter: String,
+
+    /// 延迟测试后在所有端点上同时采样最新区块的轮数，报告各端点的区块落后和时间落后分布，0 表示不采样
+    #[clap(long, default_value = "0")]
+    freshness_samples: usize,
+
+    /// 区块新鲜度两轮采样之间的间隔（毫秒）
+    #[clap(long, default_value = "1000")]
+    freshness_interval_ms: u64,
+
+    /// 开环负载测试：每个HTTP端点的目标请求速率（请求/秒），指定后代替逐个方法的延迟测试
+    #[clap(long, conflicts_with = "ramp")]
+    load_rate: Option<f64>,
+
+    /// 负载测试时长（秒）
+    #[clap(long, default_value = "30")]
+    load_duration_secs: u64,
+
+    /// 负载测试中每个端点同时在途的请求数上限，达到上限后请求排队并计入延迟
+    #[clap(long, default_value = "1000")]
+    load_max_in_flight: usize,
+
+    /// 阶梯负载测试：逗号分隔的逐级升高的负载阶段 (例如 50,100,200,400)，指定后代替逐个方法的延迟测试
+    #[clap(long)]
+    ramp: Option<String>,
+
+    /// 阶梯负载的阶段类型: rate (每秒请求数), concurrency (并发数)
+    #[clap(long, default_value = "rate")]
+    ramp_mode: String,
+
+    /// 每个阶段的时长（秒）
+    #[clap(long, default_value = "30")]
+    ramp_stage_secs: u64,
+
+    /// 拐点判定：P99 延迟上限（毫秒）
+    #[clap(long)]
+    knee_p99_ms: Option<f64>,
+
+    /// 拐点判定：P99 延迟相对第一个阶段的最大倍数
+    #[clap(long, default_value = "3.0")]
+    knee_p99_factor: f64,
+
+    /// 拐点判定：错误率上限（百分比）
+    #[clap(long, default_value = "1.0")]
+    knee_error_percent: f64,
+
+    /// 拐点判定：速率阶段中实际速率相对目标速率的最低百分比
+    #[clap(long, default_value = "90")]
+    knee_min_throughput_percent: f64,
+
+    /// 一致性检查：在固定区块上向同一网络的所有端点发送相同的查询并比较结果，指定后代替逐个方法的延迟测试
+    #[clap(long, conflicts_with_all = ["load_rate", "ramp", "duration"])]
+    consistency: bool,
+
+    /// 一致性检查使用的区块号，格式为 区块号 或 网络名称=区块号 (例如 ETH=19000000)，可重复指定
+    #[clap(long)]
+    consistency_block: Vec<String>,
+
+    /// 未指定区块号时，使用各端点最新区块的最大值减去该深度作为检查区块
+    #[clap(long, default_value = "8")]
+    consistency_depth: u64,
+
+    /// TOML 配置文件路径，命令行参数优先于文件中的值
+    #[clap(long)]
+    config: Option<PathBuf>,
+
+    /// HTTP 请求超时（秒）[默认: 10]
+    #[clap(long)]
+    http_timeout_secs: Option<u64>,
+
+    /// WebSocket 请求超时（秒）[默认: 15]
+    #[clap(long)]
+    ws_timeout_secs: Option<u64>,
+
+    /// 同一方法两轮请求之间的间隔（毫秒）[默认: 100]
+    #[clap(long)]
+    request_delay_ms: Option<u64>,
+
+    /// 在请求间隔上附加 0 到该值之间的随机抖动（毫秒）[默认: 0]
+    #[clap(long)]
+    request_jitter_ms: Option<u64>,
+
+    /// 每秒请求数上限 (令牌桶)，格式为 数值 (所有端点) 或 端点名称=数值，可重复指定
+    #[clap(long)]
+    rate_limit: Vec<String>,
+
+    /// 限速令牌桶的容量，即最多连续发出的请求数 [默认: 1]
+    #[clap(long)]
+    rate_limit_burst: Option<u32>,
+
+    /// 最大并发请求数 [默认: 10]
+    #[clap(long)]
+    max_concurrent_requests: Option<usize>,
+
+    /// 按方法覆盖请求超时，格式为 方法名=秒数，可重复指定
+    #[clap(long)]
+    method_timeout: Vec<String>,
+
+    /// 整个测试的时间上限（秒），到达后停止发出新的请求
+    #[clap(long)]
+    deadline_secs: Option<u64>,
+
     /// 日志级别 (error, warn, info, debug)
     #[clap(long, default_value = "info")]
     log_level: String,
@@ -60,15 +299,16 @@ struct Cli {
 /// 验证命令行参数
 fn validate_args(cli: &Cli) -> Result<()> {
     // 验证测试次数
-    if cli.count == 0 || cli.count > 100 {
-        anyhow::bail!("测试次数必须在1-100之间，当前值: {}", cli.count);
+    if cli.count == 0 {
+        anyhow::bail!("测试次数必须大于0");
+    }
+    if let Some(ref duration) = cli.duration {
+        config::parse_duration(duration)?;
     }
 
     // 验证URL格式
-    let urls_to_check = vec![
-        ("ETH RPC", &cli.eth_rpc),
-        ("BSC RPC", &cli.bsc_rpc),
-    ];
+    let urls_to_check = cli.eth_rpc.iter().map(|url| ("ETH RPC", url))
+        .chain(cli.bsc_rpc.iter().map(|url| ("BSC RPC", url)));
     
     for (name, url) in urls_to_check {
         if let Err(e) = Url::parse(url) {
@@ -76,25 +316,100 @@ fn validate_args(cli: &Cli) -> Result<()> {
         }
     }
 
+    // 验证可选的GraphQL和Engine API URL
+    let http_urls_to_check = cli.eth_graphql.iter().map(|url| ("ETH GraphQL", url))
+        .chain(cli.bsc_graphql.iter().map(|url| ("BSC GraphQL", url)))
+        .chain(cli.engine_rpc.iter().map(|url| ("Engine API", url)));
+
+    for (name, url) in http_urls_to_check {
+        if let Err(e) = Url::parse(url) {
+            anyhow::bail!("{} URL格式无效: {} (错误: {})", name, url, e);
+        }
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            anyhow::bail!("{} URL必须以http://或https://开头: {}", name, url);
+        }
+    }
+
     // 验证可选的WebSocket URL
-    if let Some(ref eth_ws) = cli.eth_ws {
-        if let Err(e) = Url::parse(eth_ws) {
-            anyhow::bail!("ETH WebSocket URL格式无效: {} (错误: {})", eth_ws, e);
+    let ws_urls_to_check = cli.eth_ws.iter().map(|url| ("ETH", url))
+        .chain(cli.bsc_ws.iter().map(|url| ("BSC", url)));
+
+    for (name, ws_url) in ws_urls_to_check {
+        if let Err(e) = Url::parse(ws_url) {
+            anyhow::bail!("{} WebSocket URL格式无效: {} (错误: {})", name, ws_url, e);
+        }
+        if !ws_url.starts_with("ws://") && !ws_url.starts_with("wss://") {
+            anyhow::bail!("{} WebSocket URL必须以ws://或wss://开头: {}", name, ws_url);
+        }
+    }
+
+    cli.connection_mode.parse::<rpc::ConnectionMode>()?;
+    if cli.schedule.parse::<rpc::Schedule>()? == rpc::Schedule::Method && cli.duration.is_some() {
+        anyhow::bail!("method 调度方式需要固定的测试次数，不能与 --duration 同时使用");
+    }
+    cli.compression.parse::<compression::Compression>()?;
+    net::parse_proxy_specs(&cli.proxy)?;
+    config::parse_method_timeouts(&cli.method_timeout)?;
+
+    if cli.ws_max_frame_size == 0 || cli.ws_max_frame_size > cli.ws_max_message_size {
+        anyhow::bail!("WebSocket 帧大小限制必须大于0且不超过消息大小限制");
+    }
+
+    // 验证订阅参数
+    if let Some(ref kinds) = cli.subscribe {
+        subscriptions::parse_kinds(kinds)?;
+        if cli.subscribe_secs == 0 {
+            anyhow::bail!("订阅测试时长必须大于0秒");
         }
-        if !eth_ws.starts_with("ws://") && !eth_ws.starts_with("wss://") {
-            anyhow::bail!("ETH WebSocket URL必须以ws://或wss://开头: {}", eth_ws);
+        if let Err(e) = serde_json::from_str::<serde_json::Value>(&cli.logs_filter) {
+            anyhow::bail!("logs 过滤条件不是有效的JSON: {} (错误: {})", cli.logs_filter, e);
         }
     }
 
-    if let Some(ref bsc_ws) = cli.bsc_ws {
-        if let Err(e) = Url::parse(bsc_ws) {
-            anyhow::bail!("BSC WebSocket URL格式无效: {} (错误: {})", bsc_ws, e);
+    if cli.freshness_samples > 0 && cli.freshness_interval_ms == 0 {
+        anyhow::bail!("区块新鲜度采样间隔必须大于0毫秒");
+    }
+    if cli.freshness_samples > 0 && (cli.load_rate.is_some() || cli.ramp.is_some() || cli.consistency) {
+        anyhow::bail!("区块新鲜度采样只在延迟测试后进行，不能与 --load-rate、--ramp 或 --consistency 同时使用");
+    }
+
+    // 验证负载测试参数
+    if let Some(rate) = cli.load_rate {
+        if !rate.is_finite() || rate <= 0.0 {
+            anyhow::bail!("负载测试速率必须大于0，当前值: {}", rate);
         }
-        if !bsc_ws.starts_with("ws://") && !bsc_ws.starts_with("wss://") {
-            anyhow::bail!("BSC WebSocket URL必须以ws://或wss://开头: {}", bsc_ws);
+        if cli.load_duration_secs == 0 {
+            anyhow::bail!("负载测试时长必须大于0秒");
+        }
+        if cli.load_max_in_flight == 0 {
+            anyhow::bail!("在途请求数上限必须大于0");
         }
     }
 
+    // 验证阶梯负载参数
+    if let Some(ref stages) = cli.ramp {
+        ramp::parse_stages(stages, ramp_by_concurrency(&cli.ramp_mode)?)?;
+        if cli.ramp_stage_secs == 0 {
+            anyhow::bail!("阶段时长必须大于0秒");
+        }
+        if cli.load_max_in_flight == 0 {
+            anyhow::bail!("在途请求数上限必须大于0");
+        }
+        if cli.knee_p99_ms.is_some_and(|ms| ms <= 0.0) || cli.knee_p99_factor <= 1.0 {
+            anyhow::bail!("P99 延迟阈值必须大于0，倍数阈值必须大于1");
+        }
+        if !(0.0..=100.0).contains(&cli.knee_error_percent)
+            || !(0.0..=100.0).contains(&cli.knee_min_throughput_percent)
+        {
+            anyhow::bail!("错误率和吞吐量阈值必须在0-100之间");
+        }
+    }
+
+    // 验证一致性检查参数
+    if cli.consistency {
+        consistency::ConsistencyConfig::new(&cli.consistency_block, cli.consistency_depth)?;
+    }
+
     // 验证输出目录存在
     if let Some(parent) = cli.output.parent() {
         if !parent.exists() {
@@ -105,6 +420,32 @@ fn validate_args(cli: &Cli) -> Result<()> {
     Ok(())
 }
 
+/// 解析阶梯负载的阶段类型，并发数阶段返回 true
+fn ramp_by_concurrency(mode: &str) -> Result<bool> {
+    match mode.to_lowercase().as_str() {
+        "rate" => Ok(false),
+        "concurrency" => Ok(true),
+        _ => anyhow::bail!("无效的阶段类型: {}。支持的类型: rate, concurrency", mode),
+    }
+}
+
+/// 为一组同类端点创建链配置，第二个起的端点名称附加序号 (例如 ETH-WS-2)
+fn push_chains(
+    chains: &mut Vec<chains::Chain>,
+    prefix: &str,
+    urls: &[String],
+    new_chain: fn(&str, &str) -> chains::Chain,
+) {
+    for (i, url) in urls.iter().enumerate() {
+        let name = if i == 0 {
+            prefix.to_string()
+        } else {
+            format!("{}-{}", prefix, i + 1)
+        };
+        chains.push(new_chain(&name, url));
+    }
+}
+
 /// 初始化日志系统
 fn init_logger(log_level: &str) -> Result<()> {
     let level = match log_level.to_lowercase().as_str() {
@@ -124,6 +465,163 @@ fn init_logger(log_level: &str) -> Result<()> {
     Ok(())
 }
 
+/// 逐个方法测试延迟并输出各项报告，返回期望值断言未通过的端点方法数
+async fn run_latency_test(
+    cli: &Cli,
+    chains: &[chains::Chain],
+    method_list: &[methods::RpcMethod],
+    config: &rpc::Config,
+) -> Result<usize> {
+    info!("开始执行RPC测试");
+    
+    // 执行测试
+    let length = match cli.duration {
+        Some(ref duration) => rpc::RunLength::Duration(config::parse_duration(duration)?),
+        None => rpc::RunLength::Count(cli.count),
+    };
+    let run = rpc::test_all_methods(chains, method_list, length, config.clone()).await?;
+    
+    // 计算统计数据，压缩对比模式下额外发出的请求只计入压缩对比报告
+    let stats = run.stats.method_stats();
+    
+    // 输出到CSV
+    stats::write_to_csv(&stats, &config.schedule_label(), &cli.output)?;
+    
+    // 控制台输出
+    stats::print_stats(&stats);
+    stats::print_connection_stats(&stats);
+    stats::print_phase_stats(&stats);
+    stats::print_transfer_stats(&stats);
+    if config.warmup > 0 {
+        stats::print_warmup_stats(&stats);
+    }
+    if config.retry.enabled() {
+        stats::print_retry_stats(&stats);
+    }
+    if stats.iter().any(|s| s.rate_limited_count > 0) {
+        stats::print_rate_limit_stats(&stats);
+    }
+    if stats.iter().any(|s| s.invalid_result_count > 0) {
+        stats::print_validation_stats(&stats);
+    }
+    if stats.iter().any(|s| s.assertion_pass_count + s.assertion_fail_count > 0) {
+        stats::print_assertion_stats(&stats);
+    }
+    let assertion_failures = stats.iter().filter(|s| s.assertion_fail_count > 0).count();
+    
+    // 错误汇总
+    let error_stats = run.stats.error_stats();
+    if !error_stats.is_empty() {
+        let error_output = stats::suffixed_path(&cli.output, "errors");
+        stats::write_errors_to_csv(&error_stats, &error_output)?;
+        stats::print_error_stats(&error_stats);
+        println!("错误汇总已保存到: {}", error_output.display());
+    }
+    
+    // 压缩对比
+    if config.compare_compression {
+        let compression_stats = run.stats.compression_stats();
+        let compression_output = stats::suffixed_path(&cli.output, "compression");
+        stats::write_compression_to_csv(&compression_stats, &compression_output)?;
+        stats::print_compression_stats(&compression_stats);
+        println!("压缩对比结果已保存到: {}", compression_output.display());
+    }
+    
+    // WebSocket 重连统计
+    if chains.iter().any(|c| c.connection_type == chains::ConnectionType::WebSocket) {
+        let reconnect_stats = stats::calculate_reconnect_stats(chains, &run.reconnects);
+        let reconnect_output = stats::suffixed_path(&cli.output, "reconnects");
+        stats::write_reconnects_to_csv(&reconnect_stats, &reconnect_output)?;
+        stats::print_reconnect_stats(&reconnect_stats);
+        println!("重连统计已保存到: {}", reconnect_output.display());
+    }
+    
+    // 区块新鲜度
+    if cli.freshness_samples > 0 {
+        let freshness_config = freshness::FreshnessConfig {
+            samples: cli.freshness_samples,
+            interval: std::time::Duration::from_millis(cli.freshness_interval_ms),
+        };
+        let freshness_stats = freshness::measure_freshness(chains, &freshness_config, config).await?;
+        let freshness_output = stats::suffixed_path(&cli.output, "freshness");
+        freshness::write_to_csv(&freshness_stats, &freshness_output)?;
+        freshness::print_stats(&freshness_stats);
+        println!("区块新鲜度统计已保存到: {}", freshness_output.display());
+    }
+    
+    Ok(assertion_failures)
+}
+
+/// 跨端点一致性检查并输出报告
+async fn run_consistency_check(cli: &Cli, chains: &[chains::Chain], config: &rpc::Config) -> Result<()> {
+    info!("开始执行一致性检查");
+    
+    let consistency_config = consistency::ConsistencyConfig::new(&cli.consistency_block, cli.consistency_depth)?;
+    let results = consistency::run_consistency_check(chains, &consistency_config, config).await?;
+    
+    consistency::write_to_csv(&results, &cli.output)?;
+    consistency::print_stats(&results);
+    
+    Ok(())
+}
+
+/// 开环负载测试并输出报告
+async fn run_load_test(
+    cli: &Cli,
+    rate: f64,
+    chains: &[chains::Chain],
+    method_list: &[methods::RpcMethod],
+    config: &rpc::Config,
+) -> Result<()> {
+    info!("开始执行负载测试");
+    
+    let load_config = load::LoadConfig {
+        rate,
+        duration: std::time::Duration::from_secs(cli.load_duration_secs),
+        max_in_flight: cli.load_max_in_flight,
+    };
+    let load_stats = load::run_load_test(chains, method_list, &load_config, config).await?;
+    
+    load::write_to_csv(&load_stats, &cli.output)?;
+    load::print_stats(&load_stats);
+    
+    Ok(())
+}
+
+/// 阶梯负载测试并输出各阶段和容量报告
+async fn run_ramp_test(
+    cli: &Cli,
+    stages: &str,
+    chains: &[chains::Chain],
+    method_list: &[methods::RpcMethod],
+    config: &rpc::Config,
+) -> Result<()> {
+    info!("开始执行阶梯负载测试");
+    
+    let ramp_config = ramp::RampConfig {
+        stages: ramp::parse_stages(stages, ramp_by_concurrency(&cli.ramp_mode)?)?,
+        stage_duration: std::time::Duration::from_secs(cli.ramp_stage_secs),
+        max_in_flight: cli.load_max_in_flight,
+        thresholds: ramp::KneeThresholds {
+            max_p99_ms: cli.knee_p99_ms,
+            max_p99_factor: cli.knee_p99_factor,
+            max_error_rate: cli.knee_error_percent / 100.0,
+            min_throughput_ratio: cli.knee_min_throughput_percent / 100.0,
+        },
+    };
+    let results = ramp::run_ramp_test(chains, method_list, &ramp_config, config).await?;
+    
+    ramp::write_stages_to_csv(&results, &cli.output)?;
+    ramp::print_stages(&results);
+    
+    let capacity_output = stats::suffixed_path(&cli.output, "capacity");
+    ramp::write_capacity_to_csv(&results, &capacity_output)?;
+    ramp::print_capacity(&results);
+    println!("端点容量已保存到: {}", capacity_output.display());
+    
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // 解析命令行参数
@@ -136,8 +634,8 @@ async fn main() -> Result<()> {
     validate_args(&cli)?;
     
     // 初始化方法列表
-    let method_list = if let Some(methods_str) = cli.methods {
-        let filtered_methods = methods::get_filtered_methods(&methods_str);
+    let method_list = if let Some(ref methods_str) = cli.methods {
+        let filtered_methods = methods::get_filtered_methods(methods_str);
         if filtered_methods.is_empty() {
             error!("没有找到匹配的方法: {}", methods_str);
             anyhow::bail!("没有有效的测试方法");
@@ -155,54 +653,236 @@ async fn main() -> Result<()> {
         methods::get_all_methods()
     };
     
+    // 创建链配置
+    let mut chains = Vec::new();
+    push_chains(&mut chains, "ETH-HTTP", &cli.eth_rpc, chains::Chain::new);
+    push_chains(&mut chains, "BSC-HTTP", &cli.bsc_rpc, chains::Chain::new);
+    
+    // 添加WebSocket链配置
+    push_chains(&mut chains, "ETH-WS", &cli.eth_ws, chains::Chain::new);
+    push_chains(&mut chains, "BSC-WS", &cli.bsc_ws, chains::Chain::new);
+    
+    // 添加GraphQL链配置
+    push_chains(&mut chains, "ETH-GRAPHQL", &cli.eth_graphql, chains::Chain::new_graphql);
+    push_chains(&mut chains, "BSC-GRAPHQL", &cli.bsc_graphql, chains::Chain::new_graphql);
+    
+    // 添加Engine API链配置
+    push_chains(&mut chains, "ETH-ENGINE", &cli.engine_rpc, chains::Chain::new_engine);
+    auth::apply_jwt_specs(&mut chains, &cli.jwt_secret)?;
+    
+    // 为端点分配代理，按名称指定的代理优先于默认代理
+    let (default_proxy, mut endpoint_proxies) = net::parse_proxy_specs(&cli.proxy)?;
+    for chain in &mut chains {
+        chain.proxy = endpoint_proxies.remove(&chain.name).or_else(|| default_proxy.clone());
+    }
+    chains::check_endpoint_names(&chains, endpoint_proxies.keys().map(|k| k.as_str()), "--proxy")?;
+    
+    // 为端点设置TLS配置
+    tls::apply_tls_specs(&mut chains, &tls::TlsSpecs {
+        ca: &cli.tls_ca,
+        client_cert: &cli.tls_client_cert,
+        client_key: &cli.tls_client_key,
+        pins: &cli.tls_pin,
+        insecure: &cli.tls_insecure,
+    })?;
+    
+    // 合并配置文件和命令行参数，命令行参数优先
+    let file_config = match cli.config {
+        Some(ref path) => config::FileConfig::load(path)?,
+        None => config::FileConfig::default(),
+    };
+    let defaults = rpc::Config::default();
+    let warmup = cli.warmup.or(file_config.warmup).unwrap_or(defaults.warmup);
+    
     // 打印启动信息
     println!("ETH/BSC RPC 测试工具启动");
-    println!("ETH HTTP RPC: {}", cli.eth_rpc);
-    println!("BSC HTTP RPC: {}", cli.bsc_rpc);
-    
-    if let Some(eth_ws) = &cli.eth_ws {
+    for eth_rpc in &cli.eth_rpc {
+        println!("ETH HTTP RPC: {}", eth_rpc);
+    }
+    for bsc_rpc in &cli.bsc_rpc {
+        println!("BSC HTTP RPC: {}", bsc_rpc);
+    }
+    for eth_ws in &cli.eth_ws {
         println!("ETH WebSocket: {}", eth_ws);
     }
-    
-    if let Some(bsc_ws) = &cli.bsc_ws {
+    for bsc_ws in &cli.bsc_ws {
         println!("BSC WebSocket: {}", bsc_ws);
     }
+    for eth_graphql in &cli.eth_graphql {
+        println!("ETH GraphQL: {}", eth_graphql);
+    }
+    for bsc_graphql in &cli.bsc_graphql {
+        println!("BSC GraphQL: {}", bsc_graphql);
+    }
+    for engine_rpc in &cli.engine_rpc {
+        println!("Engine API: {}", engine_rpc);
+    }
+    for chain in chains.iter().filter(|c| c.proxy.is_some()) {
+        println!("{} 代理: {}", chain.name, chain.proxy_label());
+    }
+    for chain in chains.iter().filter(|c| !c.tls.summary().is_empty()) {
+        println!("{} TLS: {}", chain.name, chain.tls.summary());
+        if chain.tls.insecure {
+            warn!("{} 已跳过证书校验，仅限实验环境使用", chain.name);
+        }
+    }
     
-    println!("测试方法数: {}", method_list.len());
-    println!("每个方法测试次数: {}", cli.count);
+    if cli.consistency {
+        println!("一致性检查: 最新区块之前 {} 个区块", cli.consistency_depth);
+    } else {
+        println!("测试方法数: {}", method_list.len());
+    }
+    match (cli.load_rate, &cli.ramp) {
+        _ if cli.consistency => {}
+        (Some(rate), _) => println!("负载测试: 每个端点 {} 请求/秒, {} 秒", rate, cli.load_duration_secs),
+        (None, Some(stages)) => println!("阶梯负载测试: {} ({}), 每个阶段 {} 秒", stages, cli.ramp_mode, cli.ramp_stage_secs),
+        (None, None) => match cli.duration {
+            Some(ref duration) => println!("耐久测试时长: {}", duration),
+            None => println!("每个方法测试次数: {}", cli.count),
+        },
+    }
+    let schedule: rpc::Schedule = cli.schedule.parse()?;
+    let schedule_seed = cli.schedule_seed.unwrap_or_else(rand::random);
+    if cli.load_rate.is_none() && cli.ramp.is_none() && !cli.consistency {
+        if warmup > 0 {
+            println!("每个方法预热次数: {}", warmup);
+        }
+        if cli.freshness_samples > 0 {
+            println!("区块新鲜度采样: {} 轮, 间隔 {} 毫秒", cli.freshness_samples, cli.freshness_interval_ms);
+        }
+        match schedule {
+            rpc::Schedule::Random => println!("调度方式: random (种子: {})", schedule_seed),
+            schedule => println!("调度方式: {}", schedule.name()),
+        }
+    }
     println!("输出文件: {}", cli.output.display());
     println!("-----------------------------");
     
-    // 创建链配置
-    let mut chains = vec![
-        chains::Chain::new("ETH-HTTP", &cli.eth_rpc),
-        chains::Chain::new("BSC-HTTP", &cli.bsc_rpc),
-    ];
-    
-    // 添加WebSocket链配置
-    if let Some(eth_ws) = &cli.eth_ws {
-        chains.push(chains::Chain::new("ETH-WS", eth_ws));
+    let mut method_timeouts = file_config.method_timeouts;
+    method_timeouts.extend(config::parse_method_timeouts(&cli.method_timeout)?);
+    let http_timeout_secs = cli.http_timeout_secs.or(file_config.http_timeout_secs).unwrap_or(defaults.http_timeout_secs);
+    let ws_timeout_secs = cli.ws_timeout_secs.or(file_config.ws_timeout_secs).unwrap_or(defaults.ws_timeout_secs);
+    let max_concurrent_requests = cli.max_concurrent_requests
+        .or(file_config.max_concurrent_requests)
+        .unwrap_or(defaults.max_concurrent_requests);
+    if http_timeout_secs == 0 || ws_timeout_secs == 0 || method_timeouts.values().any(|&secs| secs == 0) {
+        anyhow::bail!("请求超时必须大于0秒");
     }
-    
-    if let Some(bsc_ws) = &cli.bsc_ws {
-        chains.push(chains::Chain::new("BSC-WS", bsc_ws));
+    if max_concurrent_requests == 0 {
+        anyhow::bail!("最大并发请求数必须大于0");
+    }
+    let rate_limit_burst = cli.rate_limit_burst
+        .or(file_config.rate_limit_burst)
+        .unwrap_or(defaults.rate_limit_burst);
+    if rate_limit_burst == 0 {
+        anyhow::bail!("限速令牌桶容量必须大于0");
+    }
+    let retry = retry::RetryPolicy {
+        max_attempts: cli.retry_attempts.or(file_config.retry_attempts).unwrap_or(defaults.retry.max_attempts),
+        initial_backoff_ms: cli.retry_backoff_ms.or(file_config.retry_backoff_ms).unwrap_or(defaults.retry.initial_backoff_ms),
+        max_backoff_ms: cli.retry_max_backoff_ms.or(file_config.retry_max_backoff_ms).unwrap_or(defaults.retry.max_backoff_ms),
+        retryable: match cli.retry_on.as_ref().or(file_config.retry_on.as_ref()) {
+            Some(kinds) => retry::parse_retryable(kinds)?,
+            None => defaults.retry.retryable.clone(),
+        },
+    };
+    if retry.max_attempts == 0 || retry.max_attempts > 10 {
+        anyhow::bail!("最大尝试次数必须在1-10之间，当前值: {}", retry.max_attempts);
+    }
+    pacing::apply_rate_limit_specs(&mut chains, &cli.rate_limit, &file_config.rate_limits)?;
+    for chain in chains.iter().filter(|c| c.rate_limit.is_some()) {
+        println!("{} 限速: {} 请求/秒", chain.name, chain.rate_limit.unwrap_or_default());
+    }
+    let deadline_secs = cli.deadline_secs.or(file_config.deadline_secs);
+    if let Some(secs) = deadline_secs {
+        println!("测试时间上限: {}秒", secs);
     }
     
-    info!("开始执行RPC测试");
-    
-    // 执行测试
-    let results = rpc::test_all_methods(&chains, &method_list, cli.count).await?;
-    
-    // 计算统计数据
-    let stats = stats::calculate_stats(&results);
+    let config = rpc::Config {
+        http_timeout_secs,
+        ws_timeout_secs,
+        request_delay_ms: cli.request_delay_ms.or(file_config.request_delay_ms).unwrap_or(defaults.request_delay_ms),
+        request_jitter_ms: cli.request_jitter_ms.or(file_config.request_jitter_ms).unwrap_or(defaults.request_jitter_ms),
+        rate_limit_burst,
+        warmup,
+        max_concurrent_requests,
+        schedule,
+        schedule_seed,
+        method_timeouts,
+        deadline: deadline_secs.map(|secs| std::time::Instant::now() + std::time::Duration::from_secs(secs)),
+        ws_reconnect_attempts: cli.ws_reconnect_attempts,
+        ws_reconnect_backoff_ms: cli.ws_reconnect_backoff_ms,
+        ws_reconnect_max_backoff_ms: cli.ws_reconnect_max_backoff_ms,
+        ws_max_message_size: cli.ws_max_message_size,
+        ws_max_frame_size: cli.ws_max_frame_size,
+        connection_mode: cli.connection_mode.parse()?,
+        compression: cli.compression.parse()?,
+        compare_compression: cli.compare_compression,
+        honor_retry_after: cli.honor_retry_after,
+        retry,
+    };
     
-    // 输出到CSV
-    stats::write_to_csv(&stats, &cli.output)?;
+    // 期望值断言只在延迟测试中检查
+    let assertion_failures = match (cli.load_rate, &cli.ramp) {
+        _ if cli.consistency => {
+            run_consistency_check(&cli, &chains, &config).await?;
+            0
+        }
+        (Some(rate), _) => {
+            run_load_test(&cli, rate, &chains, &method_list, &config).await?;
+            0
+        }
+        (None, Some(stages)) => {
+            run_ramp_test(&cli, stages, &chains, &method_list, &config).await?;
+            0
+        }
+        (None, None) => run_latency_test(&cli, &chains, &method_list, &config).await?,
+    };
     
-    // 控制台输出
-    stats::print_stats(&stats);
+    // 订阅吞吐量测试
+    if let Some(ref kinds_str) = cli.subscribe {
+        let kinds = subscriptions::parse_kinds(kinds_str)?;
+        let logs_filter: serde_json::Value = serde_json::from_str(&cli.logs_filter)?;
+        
+        // 有截止时间时，每种订阅的测试时长不超过剩余时间的平均分配
+        let mut subscribe_duration = std::time::Duration::from_secs(cli.subscribe_secs);
+        if let Some(remaining) = config.remaining() {
+            subscribe_duration = subscribe_duration.min(remaining / kinds.len() as u32);
+        }
+        
+        if subscribe_duration.is_zero() {
+            warn!("已到达测试截止时间，跳过订阅测试");
+        } else if chains.iter().any(|c| c.connection_type == chains::ConnectionType::WebSocket) {
+            println!("-----------------------------");
+            let subscription_run = subscriptions::test_subscriptions(
+                &chains,
+                &kinds,
+                &logs_filter,
+                subscribe_duration,
+                config,
+            ).await;
+            
+            let subscription_output = stats::suffixed_path(&cli.output, "subscriptions");
+            subscriptions::write_to_csv(&subscription_run.stats, &subscription_output)?;
+            subscriptions::print_stats(&subscription_run.stats);
+            println!("订阅测试结果已保存到: {}", subscription_output.display());
+            
+            // 订阅长连接的重连统计
+            let reconnect_stats = stats::calculate_reconnect_stats(&chains, &subscription_run.reconnects);
+            let reconnect_output = stats::suffixed_path(&cli.output, "subscription-reconnects");
+            stats::write_reconnects_to_csv(&reconnect_stats, &reconnect_output)?;
+            stats::print_reconnect_stats(&reconnect_stats);
+            println!("订阅重连统计已保存到: {}", reconnect_output.display());
+        } else {
+            warn!("未配置WebSocket端点，跳过订阅测试");
+        }
+    }
     
     println!("\n测试完成！结果已保存到: {}", cli.output.display());
     
+    if assertion_failures > 0 {
+        anyhow::bail!("{} 个端点方法的期望值断言未通过", assertion_failures);
+    }
+    
     Ok(())
 }
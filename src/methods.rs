@@ -1,3 +1,5 @@
+use crate::assertion::Assertion;
+use crate::validate::Validator;
 use serde_json::json;
 
 /// 测试用的常见地址常量
@@ -6,8 +8,10 @@ pub mod test_addresses {
     pub const VITALIK_ADDRESS: &str = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045";
     /// DAI稳定币合约地址
     pub const DAI_CONTRACT: &str = "0x6b175474e89094c44da98b954eedeac495271d0f";
-    /// USDC稳定币合约地址
-    pub const USDC_CONTRACT: &str = "0xa0b86a33e6c5bb4d9b0c44a8b5ec23c9b02a1a8a";
+    /// BSC 上的 Binance 热钱包地址
+    pub const BSC_HOT_WALLET: &str = "0x8894e0a0c962cb723c1976a4421c95949be2d4e3";
+    /// BSC 上的 WBNB 合约地址
+    pub const WBNB_CONTRACT: &str = "0xbb4cdb9cbd36b01bd1cbaebf2de08d9173bc095c";
 }
 
 /// 表示一个 RPC 方法及其参数
@@ -19,6 +23,12 @@ pub struct RpcMethod {
     pub params: Vec<serde_json::Value>,
     /// 方法描述
     pub description: String,
+    /// 等价的 EIP-1767 GraphQL 查询，没有等价查询的方法不在 GraphQL 端点上测试
+    pub graphql: Option<String>,
+    /// `result` 的校验规则，None 表示只要没有错误即视为成功
+    pub validator: Option<Validator>,
+    /// 结果的期望值断言，只检查适用于端点所属网络的断言
+    pub assertions: Vec<Assertion>,
 }
 
 impl RpcMethod {
@@ -28,8 +38,60 @@ impl RpcMethod {
             name: name.to_string(),
             params,
             description: description.to_string(),
+            graphql: graphql_query(name),
+            validator: Validator::for_method(name),
+            assertions: Vec::new(),
         }
     }
+
+    /// 添加一个期望值断言
+    pub fn expect(mut self, assertion: Assertion) -> Self {
+        self.assertions.push(assertion);
+        self
+    }
+}
+
+/// 与 JSON-RPC 方法等价的 EIP-1767 GraphQL 查询
+///
+/// 查询使用与 JSON-RPC 参数相同的地址和最新区块，返回的字段覆盖 JSON-RPC 响应中的主要数据，
+/// 以便两种接口的延迟可以直接比较。
+fn graphql_query(method: &str) -> Option<String> {
+    use test_addresses::*;
+    
+    let query = match method {
+        "eth_blockNumber" => "{ block { number } }".to_string(),
+        "eth_chainId" => "{ chainID }".to_string(),
+        "eth_gasPrice" => "{ gasPrice }".to_string(),
+        "eth_syncing" => "{ syncing { startingBlock currentBlock highestBlock } }".to_string(),
+        "eth_getBalance" => format!(r#"{{ block {{ account(address: "{}") {{ balance }} }} }}"#, VITALIK_ADDRESS),
+        "eth_getTransactionCount" => {
+            format!(r#"{{ block {{ account(address: "{}") {{ transactionCount }} }} }}"#, VITALIK_ADDRESS)
+        }
+        "eth_getCode" => format!(r#"{{ block {{ account(address: "{}") {{ code }} }} }}"#, DAI_CONTRACT),
+        "eth_getStorageAt" => format!(
+            r#"{{ block {{ account(address: "{}") {{ storage(slot: "0x{:064x}") }} }} }}"#,
+            DAI_CONTRACT, 0
+        ),
+        "eth_getBlockByNumber" => "{ block { number hash parent { hash } timestamp gasLimit gasUsed baseFeePerGas \
+            miner { address } transactions { hash } } }"
+            .to_string(),
+        "eth_getBlockTransactionCountByNumber" => "{ block { transactionCount } }".to_string(),
+        "eth_getUncleCountByBlockNumber" => "{ block { ommerCount } }".to_string(),
+        "eth_call" => format!(
+            r#"{{ block {{ call(data: {{ to: "{}", data: "0x06fdde03" }}) {{ data status gasUsed }} }} }}"#,
+            DAI_CONTRACT
+        ),
+        "eth_estimateGas" => format!(
+            r#"{{ block {{ estimateGas(data: {{ to: "{}", data: "0x06fdde03" }}) }} }}"#,
+            DAI_CONTRACT
+        ),
+        "eth_getLogs" => format!(
+            r#"{{ block {{ logs(filter: {{ addresses: ["{}"], topics: [] }}) {{ index data topics transaction {{ hash }} }} }} }}"#,
+            DAI_CONTRACT
+        ),
+        _ => return None,
+    };
+    Some(query)
 }
 
 /// 获取所有支持的 RPC 方法
@@ -61,12 +123,14 @@ fn get_web3_methods() -> Vec<RpcMethod> {
             "web3_clientVersion",
             vec![],
             "获取客户端版本信息",
-        ),
+        )
+        .expect(Assertion::matches(r"^[\w.-]+/")), // 客户端名称/版本/...
         RpcMethod::new(
             "web3_sha3",
             vec![json!("0x68656c6c6f20776f726c64")], // "hello world"的十六进制
             "计算输入数据的Keccak-256哈希值",
-        ),
+        )
+        .expect(Assertion::equals(json!("0x47173285a8d7341e5e972fc677286384f802f8ef42a5ec5f03bbfa254cb01fad"))),
     ]
 }
 
@@ -77,7 +141,9 @@ fn get_net_methods() -> Vec<RpcMethod> {
             "net_version",
             vec![],
             "获取当前网络ID",
-        ),
+        )
+        .expect(Assertion::equals(json!("1")).on("ETH"))
+        .expect(Assertion::equals(json!("56")).on("BSC")),
         RpcMethod::new(
             "net_listening",
             vec![],
@@ -138,7 +204,9 @@ fn get_eth_basic_methods() -> Vec<RpcMethod> {
             "eth_chainId",
             vec![],
             "获取链ID",
-        ),
+        )
+        .expect(Assertion::equals(json!("0x1")).on("ETH"))
+        .expect(Assertion::equals(json!("0x38")).on("BSC")),
     ]
 }
 
@@ -200,7 +268,8 @@ fn get_eth_advanced_methods() -> Vec<RpcMethod> {
                 json!("latest")
             ],
             "调用合约只读方法 (获取DAI代币名称)",
-        ),
+        )
+        .expect(Assertion::abi_string("Dai Stablecoin").on("ETH")),
         RpcMethod::new(
             "eth_estimateGas",
             vec![
@@ -215,7 +284,8 @@ fn get_eth_advanced_methods() -> Vec<RpcMethod> {
             "eth_feeHistory",
             vec![json!("0x1"), json!("latest"), json!([25, 50, 75])],
             "获取最近区块的fee历史 (EIP-1559相关)",
-        ),
+        )
+        .expect(Assertion::exists().at("$.baseFeePerGas[1]")), // 请求 1 个区块时返回 2 个值，包括下一个区块
         RpcMethod::new(
             "eth_getLogs",
             vec![
@@ -247,9 +317,33 @@ pub fn get_basic_methods() -> Vec<RpcMethod> {
     vec![
         RpcMethod::new("eth_blockNumber", vec![], "获取最新区块号"),
         RpcMethod::new("eth_gasPrice", vec![], "获取当前gas价格"),
-        RpcMethod::new("eth_chainId", vec![], "获取链ID"),
-        RpcMethod::new("net_version", vec![], "获取网络ID"),
-        RpcMethod::new("web3_clientVersion", vec![], "获取客户端版本"),
+        RpcMethod::new("eth_chainId", vec![], "获取链ID")
+            .expect(Assertion::equals(json!("0x1")).on("ETH"))
+            .expect(Assertion::equals(json!("0x38")).on("BSC")),
+        RpcMethod::new("net_version", vec![], "获取网络ID")
+            .expect(Assertion::equals(json!("1")).on("ETH"))
+            .expect(Assertion::equals(json!("56")).on("BSC")),
+        RpcMethod::new("web3_clientVersion", vec![], "获取客户端版本").expect(Assertion::matches(r"^[\w.-]+/")),
+    ]
+}
+
+/// 获取 Engine API 测试方法，在需要 JWT 认证的端口上执行
+pub fn get_engine_methods() -> Vec<RpcMethod> {
+    vec![
+        RpcMethod::new(
+            "engine_exchangeCapabilities",
+            vec![json!([
+                "engine_exchangeCapabilities",
+                "engine_getPayloadBodiesByRangeV1",
+            ])],
+            "交换支持的Engine API方法",
+        ),
+        RpcMethod::new(
+            "engine_getPayloadBodiesByRangeV1",
+            vec![json!("0x1"), json!("0x1")],
+            "按区块范围获取执行负载体",
+        ),
+        RpcMethod::new("eth_syncing", vec![], "检查同步状态"),
     ]
 }
 
@@ -0,0 +1,228 @@
+use crate::chains::split_endpoint_spec;
+use crate::rpc::RpcError;
+use anyhow::Result;
+use base64::Engine;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_socks::tcp::Socks5Stream;
+use url::Url;
+
+/// 代理类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProxyKind {
+    /// HTTP 代理，通过 CONNECT 建立隧道
+    Http,
+    /// SOCKS5 代理，目标域名在本地解析
+    Socks5,
+    /// SOCKS5 代理，目标域名由代理解析
+    Socks5h,
+}
+
+/// 一个端点使用的代理
+#[derive(Debug, Clone)]
+pub struct ProxyConfig {
+    url: Url,
+    kind: ProxyKind,
+}
+
+impl ProxyConfig {
+    /// 代理主机名
+    pub fn host(&self) -> &str {
+        self.url.host_str().unwrap_or_default()
+    }
+
+    /// 代理端口
+    pub fn port(&self) -> u16 {
+        self.url.port_or_known_default().unwrap_or(match self.kind {
+            ProxyKind::Http => 8080,
+            ProxyKind::Socks5 | ProxyKind::Socks5h => 1080,
+        })
+    }
+
+    /// 代理认证信息 (用户名, 密码)
+    fn credentials(&self) -> Option<(String, String)> {
+        if self.url.username().is_empty() {
+            return None;
+        }
+        let decode = |s: &str| percent_encoding::percent_decode_str(s).decode_utf8_lossy().into_owned();
+        Some((
+            decode(self.url.username()),
+            decode(self.url.password().unwrap_or_default()),
+        ))
+    }
+
+    /// 隐去密码后的代理地址，用于日志和报告
+    pub fn redacted(&self) -> String {
+        let mut url = self.url.clone();
+        if url.password().is_some() {
+            let _ = url.set_password(Some("***"));
+        }
+        url.as_str().trim_end_matches('/').to_string()
+    }
+
+    /// 转换为 reqwest 的代理配置
+    pub fn to_reqwest(&self) -> Result<reqwest::Proxy, RpcError> {
+        reqwest::Proxy::all(self.url.as_str())
+            .map_err(|e| RpcError::ConfigError(format!("无效的代理: {} ({})", self.redacted(), e)))
+    }
+}
+
+impl std::str::FromStr for ProxyConfig {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let url = Url::parse(s).map_err(|e| anyhow::anyhow!("代理URL格式无效: {} (错误: {})", s, e))?;
+        let kind = match url.scheme() {
+            "http" => ProxyKind::Http,
+            "socks5" => ProxyKind::Socks5,
+            "socks5h" => ProxyKind::Socks5h,
+            scheme => anyhow::bail!("不支持的代理协议: {}。支持的协议: http, socks5, socks5h", scheme),
+        };
+        if url.host_str().is_none() {
+            anyhow::bail!("代理URL缺少主机名: {}", s);
+        }
+        Ok(Self { url, kind })
+    }
+}
+
+/// 解析 `--proxy` 参数，格式为 `URL`（所有端点的默认代理）或 `端点名称=URL`
+///
+/// 返回默认代理和按端点名称指定的代理。
+pub fn parse_proxy_specs(specs: &[String]) -> Result<(Option<ProxyConfig>, HashMap<String, ProxyConfig>)> {
+    let mut default = None;
+    let mut by_endpoint = HashMap::new();
+
+    for spec in specs {
+        match split_endpoint_spec(spec) {
+            (Some(name), url) => {
+                if by_endpoint.insert(name.to_string(), url.parse()?).is_some() {
+                    anyhow::bail!("端点 {} 重复指定了代理", name);
+                }
+            }
+            (None, url) => {
+                if default.replace(url.parse()?).is_some() {
+                    anyhow::bail!("默认代理只能指定一个");
+                }
+            }
+        }
+    }
+
+    Ok((default, by_endpoint))
+}
+
+/// 解析主机名
+pub async fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>, RpcError> {
+    let addrs: Vec<_> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| RpcError::NetworkError(format!("DNS解析失败: {} ({})", host, e)))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(RpcError::NetworkError(format!("DNS解析失败: {} 没有可用地址", host)));
+    }
+    Ok(addrs)
+}
+
+/// 依次尝试连接解析到的地址
+pub async fn connect_addrs(addrs: &[SocketAddr]) -> Result<TcpStream, RpcError> {
+    let mut last_error = None;
+    for addr in addrs {
+        match TcpStream::connect(addr).await {
+            Ok(stream) => {
+                let _ = stream.set_nodelay(true);
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(RpcError::NetworkError(format!(
+        "TCP连接失败: {}",
+        last_error.map(|e| e.to_string()).unwrap_or_else(|| "没有可用地址".to_string())
+    )))
+}
+
+/// 在到代理的连接上建立到目标主机的隧道
+pub async fn open_tunnel(
+    mut stream: TcpStream,
+    proxy: &ProxyConfig,
+    host: &str,
+    port: u16,
+) -> Result<TcpStream, RpcError> {
+    let proxy_error = |e: String| RpcError::NetworkError(format!("代理 {} 连接失败: {}", proxy.redacted(), e));
+
+    match proxy.kind {
+        ProxyKind::Http => {
+            let authority = format!("{}:{}", host, port);
+            let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+            if let Some((username, password)) = proxy.credentials() {
+                let token = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+                request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", token));
+            }
+            request.push_str("\r\n");
+            stream
+                .write_all(request.as_bytes())
+                .await
+                .map_err(|e| proxy_error(e.to_string()))?;
+
+            // 逐字节读取响应头，避免读走隧道中的数据
+            let mut head = Vec::new();
+            let mut byte = [0u8; 1];
+            while !head.ends_with(b"\r\n\r\n") {
+                if head.len() > 8192 {
+                    return Err(proxy_error("CONNECT 响应头过长".to_string()));
+                }
+                let n = stream.read(&mut byte).await.map_err(|e| proxy_error(e.to_string()))?;
+                if n == 0 {
+                    return Err(proxy_error("代理在返回 CONNECT 响应前关闭连接".to_string()));
+                }
+                head.push(byte[0]);
+            }
+
+            let head = String::from_utf8_lossy(&head);
+            let status_line = head.lines().next().unwrap_or_default();
+            if status_line.split_whitespace().nth(1) != Some("200") {
+                return Err(proxy_error(format!("CONNECT 被拒绝: {}", status_line)));
+            }
+            Ok(stream)
+        }
+        ProxyKind::Socks5 | ProxyKind::Socks5h => {
+            let credentials = proxy.credentials();
+            let result = if proxy.kind == ProxyKind::Socks5 {
+                let target = resolve(host, port).await?[0];
+                match credentials {
+                    Some((username, password)) => {
+                        Socks5Stream::connect_with_password_and_socket(stream, target, &username, &password).await
+                    }
+                    None => Socks5Stream::connect_with_socket(stream, target).await,
+                }
+            } else {
+                match credentials {
+                    Some((username, password)) => {
+                        Socks5Stream::connect_with_password_and_socket(stream, (host, port), &username, &password)
+                            .await
+                    }
+                    None => Socks5Stream::connect_with_socket(stream, (host, port)).await,
+                }
+            };
+            result
+                .map(|socks_stream| socks_stream.into_inner())
+                .map_err(|e| proxy_error(e.to_string()))
+        }
+    }
+}
+
+/// 建立到目标主机的 TCP 连接，配置了代理时经代理建立隧道
+pub async fn connect(host: &str, port: u16, proxy: Option<&ProxyConfig>) -> Result<TcpStream, RpcError> {
+    match proxy {
+        Some(proxy) => {
+            let addrs = resolve(proxy.host(), proxy.port()).await?;
+            let stream = connect_addrs(&addrs).await?;
+            open_tunnel(stream, proxy, host, port).await
+        }
+        None => {
+            let addrs = resolve(host, port).await?;
+            connect_addrs(&addrs).await
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crate::chains::{check_endpoint_names, split_endpoint_spec, Chain};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// 令牌桶限速器，限制一个端点每秒发出的请求数
+///
+/// 令牌以固定速率补充，最多积累 `burst` 个；每个请求（包括重试）发送前取得一个令牌，
+/// 令牌不足时等待，等待时间不计入请求延迟。
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// 创建每秒 `rate` 个请求、最多连续发出 `burst` 个请求的限速器，初始时令牌是满的
+    pub fn new(rate: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            rate,
+            burst,
+            tokens: burst,
+            updated: Instant::now(),
+        }
+    }
+
+    /// 取得一个令牌，令牌不足时等待
+    pub async fn acquire(&mut self) {
+        self.refill();
+        if self.tokens < 1.0 {
+            tokio::time::sleep(Duration::from_secs_f64((1.0 - self.tokens) / self.rate)).await;
+            self.refill();
+        }
+        // 定时器的误差可能使令牌略少于 1，差额计入下一次等待
+        self.tokens -= 1.0;
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.updated = now;
+    }
+}
+
+/// 解析 `--rate-limit` 参数并设置到端点
+///
+/// 参数格式为 `每秒请求数`（所有端点）或 `端点名称=每秒请求数`，按名称指定的限速优先，
+/// 其次是配置文件 `[rate_limits]` 中按端点名称指定的限速。
+pub fn apply_rate_limit_specs(chains: &mut [Chain], specs: &[String], file_limits: &HashMap<String, f64>) -> Result<()> {
+    let mut default = None;
+    let mut by_endpoint = file_limits.clone();
+    let mut from_cli = HashMap::new();
+    for spec in specs {
+        match split_endpoint_spec(spec) {
+            (Some(name), rate) => {
+                if from_cli.insert(name.to_string(), parse_rate(rate)?).is_some() {
+                    anyhow::bail!("端点 {} 重复指定了限速", name);
+                }
+            }
+            (None, rate) => {
+                if default.replace(parse_rate(rate)?).is_some() {
+                    anyhow::bail!("默认限速只能指定一个");
+                }
+            }
+        }
+    }
+    by_endpoint.extend(from_cli);
+
+    if let Some(rate) = file_limits.values().find(|&&rate| !rate.is_finite() || rate <= 0.0) {
+        anyhow::bail!("配置文件中的限速必须大于0: {}", rate);
+    }
+    check_endpoint_names(chains, by_endpoint.keys().map(|k| k.as_str()), "--rate-limit")?;
+
+    for chain in chains.iter_mut() {
+        chain.rate_limit = by_endpoint.get(&chain.name).copied().or(default);
+    }
+
+    Ok(())
+}
+
+fn parse_rate(value: &str) -> Result<f64> {
+    let rate: f64 = value
+        .trim()
+        .parse()
+        .with_context(|| format!("限速必须是每秒请求数: {}", value))?;
+    if !rate.is_finite() || rate <= 0.0 {
+        anyhow::bail!("限速必须大于0: {}", value);
+    }
+    Ok(rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chains() -> Vec<Chain> {
+        vec![Chain::new_http("ETH-HTTP", "http://a:8545"), Chain::new_http("ETH-HTTP-2", "http://b:8545")]
+    }
+
+    fn specs(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn bucket_refills_at_rate_and_caps_at_burst() {
+        let mut bucket = TokenBucket::new(10.0, 3);
+        assert_eq!(bucket.tokens, 3.0);
+        bucket.tokens = 0.0;
+
+        bucket.updated = Instant::now() - Duration::from_millis(150);
+        bucket.refill();
+        assert!((1.4..1.6).contains(&bucket.tokens), "tokens = {}", bucket.tokens);
+
+        bucket.updated = Instant::now() - Duration::from_secs(60);
+        bucket.refill();
+        assert_eq!(bucket.tokens, 3.0);
+    }
+
+    #[test]
+    fn zero_burst_is_treated_as_one() {
+        assert_eq!(TokenBucket::new(5.0, 0).burst, 1.0);
+    }
+
+    #[tokio::test]
+    async fn acquire_spends_burst_without_waiting() {
+        let mut bucket = TokenBucket::new(1.0, 3);
+        let start = Instant::now();
+        for _ in 0..3 {
+            bucket.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(500));
+        assert!(bucket.tokens < 1.0);
+    }
+
+    #[test]
+    fn named_spec_overrides_default_and_file() {
+        let mut named = chains();
+        let file = HashMap::from([("ETH-HTTP".to_string(), 5.0), ("ETH-HTTP-2".to_string(), 7.0)]);
+        apply_rate_limit_specs(&mut named, &specs(&["10", "ETH-HTTP-2=25"]), &file).unwrap();
+        assert_eq!(named[0].rate_limit, Some(5.0));
+        assert_eq!(named[1].rate_limit, Some(25.0));
+
+        let mut defaulted = chains();
+        apply_rate_limit_specs(&mut defaulted, &specs(&["10"]), &HashMap::new()).unwrap();
+        assert_eq!(defaulted[0].rate_limit, Some(10.0));
+        assert_eq!(defaulted[1].rate_limit, Some(10.0));
+    }
+
+    #[test]
+    fn invalid_specs_are_rejected() {
+        for bad in [&["0"][..], &["abc"], &["1", "2"], &["ETH-HTTP=1", "ETH-HTTP=2"], &["NOPE=1"]] {
+            assert!(apply_rate_limit_specs(&mut chains(), &specs(bad), &HashMap::new()).is_err(), "{:?}", bad);
+        }
+        let file = HashMap::from([("ETH-HTTP".to_string(), -1.0)]);
+        assert!(apply_rate_limit_specs(&mut chains(), &[], &file).is_err());
+    }
+}
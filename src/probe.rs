@@ -0,0 +1,375 @@
+use crate::chains::Chain;
+use crate::net;
+use crate::rpc::{PhaseTimings, RpcError};
+use log::debug;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use url::Url;
+
+/// 带分阶段计时的 HTTP 响应
+#[derive(Debug)]
+pub struct ProbeResponse {
+    /// HTTP 状态码
+    pub status: u16,
+    /// 响应的 Retry-After
+    pub retry_after: Option<String>,
+    /// 响应体（未解压）
+    pub body: Vec<u8>,
+    /// 响应的 Content-Encoding
+    pub content_encoding: Option<String>,
+    /// 各阶段耗时
+    pub phases: PhaseTimings,
+}
+
+fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+/// 在全新连接上发送一个 HTTP/1.1 POST 请求，并记录 DNS、TCP、TLS、写请求、首字节和响应体各阶段耗时
+///
+/// reqwest 不暴露连接建立的细节，因此冷连接请求使用这个最小化的 HTTP 客户端。
+/// 热连接请求仍经 reqwest 发送，冷、热连接延迟之差因此还包含两个 HTTP 实现本身的差异。
+/// 经代理连接时，DNS 阶段解析的是代理主机，TCP 连接阶段包含建立代理隧道的耗时。
+/// 端点的 TLS 配置（CA、客户端证书、证书指纹）同样生效，指纹在发送请求前校验。
+pub async fn post_json(
+    chain: &Chain,
+    body: &[u8],
+    accept_encoding: Option<&str>,
+    timeout: Duration,
+) -> Result<ProbeResponse, RpcError> {
+    tokio::time::timeout(timeout, post_json_inner(chain, body, accept_encoding))
+        .await
+        .map_err(|_| RpcError::TimeoutError(format!("请求超时({}秒)", timeout.as_secs())))?
+}
+
+async fn post_json_inner(
+    chain: &Chain,
+    body: &[u8],
+    accept_encoding: Option<&str>,
+) -> Result<ProbeResponse, RpcError> {
+    let proxy = chain.proxy.as_ref();
+    let url = Url::parse(&chain.rpc_url).map_err(|e| RpcError::ConfigError(format!("无效的URL: {}", e)))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| RpcError::ConfigError(format!("URL缺少主机名: {}", url)))?
+        .to_string();
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| RpcError::ConfigError(format!("URL缺少端口: {}", url)))?;
+
+    let mut phases = PhaseTimings::default();
+
+    // DNS 解析
+    let start = Instant::now();
+    let addrs = match proxy {
+        Some(proxy) => net::resolve(proxy.host(), proxy.port()).await?,
+        None => net::resolve(&host, port).await?,
+    };
+    phases.dns_ms = Some(elapsed_ms(start));
+
+    // TCP 连接，经代理时还包括建立隧道
+    let start = Instant::now();
+    let mut tcp_stream = net::connect_addrs(&addrs).await?;
+    if let Some(proxy) = proxy {
+        tcp_stream = net::open_tunnel(tcp_stream, proxy, &host, port).await?;
+    }
+    phases.connect_ms = Some(elapsed_ms(start));
+
+    let mut request_head = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nUser-Agent: eth-rpc-check\r\nConnection: close\r\n",
+        request_target(&url),
+        host_header(&url, &host),
+        body.len()
+    );
+    if let Some(encoding) = accept_encoding {
+        request_head.push_str(&format!("Accept-Encoding: {}\r\n", encoding));
+    }
+    if let Some(ref secret) = chain.jwt_secret {
+        request_head.push_str(&format!("Authorization: {}\r\n", secret.authorization()));
+    }
+    request_head.push_str("\r\n");
+
+    if url.scheme() == "https" {
+        // TLS 握手
+        let start = Instant::now();
+        let tls_stream = chain.tls.handshake(&host, tcp_stream).await?;
+        phases.tls_ms = Some(elapsed_ms(start));
+
+        exchange(tls_stream, request_head.as_bytes(), body, phases).await
+    } else {
+        exchange(tcp_stream, request_head.as_bytes(), body, phases).await
+    }
+}
+
+/// 在全新连接上完成 TLS 握手并校验证书指纹，不发送任何请求数据
+///
+/// reqwest 只能在收到响应后取得对端证书，配置了证书指纹的 HTTPS 端点在发送第一个请求前先用它校验，
+/// 避免请求体和 JWT 认证头发往未经校验的对端。
+pub async fn check_pin(chain: &Chain, timeout: Duration) -> Result<(), RpcError> {
+    let check = async {
+        let url = Url::parse(&chain.rpc_url).map_err(|e| RpcError::ConfigError(format!("无效的URL: {}", e)))?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| RpcError::ConfigError(format!("URL缺少主机名: {}", url)))?;
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| RpcError::ConfigError(format!("URL缺少端口: {}", url)))?;
+        let tcp_stream = net::connect(host, port, chain.proxy.as_ref()).await?;
+        chain.tls.handshake(host, tcp_stream).await?;
+        debug!("{} 证书指纹校验通过", chain.name);
+        Ok(())
+    };
+    tokio::time::timeout(timeout, check)
+        .await
+        .map_err(|_| RpcError::TimeoutError(format!("证书指纹校验超时({}秒)", timeout.as_secs())))?
+}
+
+/// 请求行中的路径和查询参数
+fn request_target(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// Host 请求头，非默认端口时附带端口号
+fn host_header(url: &Url, host: &str) -> String {
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    }
+}
+
+/// 写入请求并读取完整响应
+async fn exchange<S>(
+    mut stream: S,
+    request_head: &[u8],
+    body: &[u8],
+    mut phases: PhaseTimings,
+) -> Result<ProbeResponse, RpcError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let io_error = |e: std::io::Error| RpcError::NetworkError(format!("HTTP请求失败: {}", e));
+
+    // 写入请求
+    let start = Instant::now();
+    stream.write_all(request_head).await.map_err(io_error)?;
+    stream.write_all(body).await.map_err(io_error)?;
+    stream.flush().await.map_err(io_error)?;
+    phases.request_write_ms = Some(elapsed_ms(start));
+
+    // 等待首字节
+    let start = Instant::now();
+    let mut buffer = Vec::with_capacity(8192);
+    let mut chunk = [0u8; 8192];
+    let n = stream.read(&mut chunk).await.map_err(io_error)?;
+    if n == 0 {
+        return Err(RpcError::NetworkError("连接在返回响应前关闭".to_string()));
+    }
+    phases.ttfb_ms = Some(elapsed_ms(start));
+    buffer.extend_from_slice(&chunk[..n]);
+
+    // 读取响应头和响应体，每次只从上次搜索结束的位置继续查找响应头结尾
+    let start = Instant::now();
+    let mut searched = 0;
+    let header_end = loop {
+        if let Some(pos) = find_subsequence(&buffer[searched..], b"\r\n\r\n") {
+            break searched + pos;
+        }
+        if buffer.len() > MAX_HEADER_SIZE {
+            return Err(RpcError::NetworkError(format!("响应头超过 {} 字节", MAX_HEADER_SIZE)));
+        }
+        searched = buffer.len().saturating_sub(3);
+        let n = stream.read(&mut chunk).await.map_err(io_error)?;
+        if n == 0 {
+            return Err(RpcError::NetworkError("响应头不完整".to_string()));
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| RpcError::NetworkError("无效的HTTP状态行".to_string()))?;
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let mut raw_body = buffer.split_off(header_end + 4);
+    let chunked = headers
+        .iter()
+        .any(|(name, value)| name == "transfer-encoding" && value.to_lowercase().contains("chunked"));
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse::<usize>().ok());
+
+    let mut chunked_decoder = ChunkedDecoder::default();
+    let body = loop {
+        if chunked {
+            if chunked_decoder.decode(&raw_body)? {
+                break chunked_decoder.decoded;
+            }
+        } else if let Some(length) = content_length {
+            if raw_body.len() >= length {
+                raw_body.truncate(length);
+                break raw_body;
+            }
+        }
+
+        let n = stream.read(&mut chunk).await.map_err(io_error)?;
+        if n == 0 {
+            if chunked || content_length.is_some() {
+                return Err(RpcError::NetworkError("响应体不完整".to_string()));
+            }
+            // 既没有 Content-Length 也不是分块编码时，响应体在连接关闭时结束
+            break raw_body;
+        }
+        raw_body.extend_from_slice(&chunk[..n]);
+    };
+    phases.body_ms = Some(elapsed_ms(start));
+
+    debug!("HTTP {} 响应 {} 字节, 阶段耗时: {:?}", status, body.len(), phases);
+
+    let header = |wanted: &str| {
+        headers
+            .iter()
+            .find(|(name, _)| name == wanted)
+            .map(|(_, value)| value.clone())
+    };
+
+    Ok(ProbeResponse {
+        status,
+        retry_after: header("retry-after"),
+        content_encoding: header("content-encoding"),
+        body,
+        phases,
+    })
+}
+
+fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// 响应头的长度上限，超过时视为网络错误，避免不断接收永不结束的响应头
+const MAX_HEADER_SIZE: usize = 64 * 1024;
+
+/// 分块大小行（包括扩展字段）的长度上限，超过时视为格式错误
+const MAX_CHUNK_SIZE_LINE: usize = 1024;
+
+/// 分块传输编码的增量解码器
+///
+/// 保存已解码的位置，每次读取后只处理新到达的数据；分块数据完整到达后才解码该分块，
+/// 大响应体的解码耗时与长度成正比，不会因为反复从头解码而计入过多的读取响应体耗时。
+#[derive(Debug, Default)]
+struct ChunkedDecoder {
+    decoded: Vec<u8>,
+    /// 下一个分块大小行在原始数据中的位置
+    pos: usize,
+}
+
+impl ChunkedDecoder {
+    /// 从上次停下的位置继续解码 `data`，解码完成时返回 true，数据尚不完整时返回 false，
+    /// 分块格式错误时返回错误
+    fn decode(&mut self, data: &[u8]) -> Result<bool, RpcError> {
+        loop {
+            let pending = &data[self.pos..];
+            let Some(line_len) = find_subsequence(pending, b"\r\n") else {
+                if pending.len() > MAX_CHUNK_SIZE_LINE {
+                    return Err(malformed_chunk("分块大小行过长"));
+                }
+                return Ok(false);
+            };
+            let size_line = std::str::from_utf8(&pending[..line_len])
+                .map_err(|_| malformed_chunk("分块大小行不是有效的文本"))?;
+            let size_str = size_line.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size_str, 16)
+                .map_err(|_| malformed_chunk(&format!("无效的分块大小 {:?}", size_str)))?;
+            let start = self.pos + line_len + 2;
+
+            if size == 0 {
+                // 忽略尾部字段，只要求结束的空行已经到达
+                return Ok(find_subsequence(&data[start..], b"\r\n").is_some());
+            }
+
+            let end = start
+                .checked_add(size)
+                .ok_or_else(|| malformed_chunk("分块大小溢出"))?;
+            if data.len() < end + 2 {
+                return Ok(false);
+            }
+            if &data[end..end + 2] != b"\r\n" {
+                return Err(malformed_chunk("分块数据后缺少 CRLF"));
+            }
+            self.decoded.extend_from_slice(&data[start..end]);
+            self.pos = end + 2;
+        }
+    }
+}
+
+fn malformed_chunk(reason: &str) -> RpcError {
+    RpcError::NetworkError(format!("分块传输编码格式错误: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 通过每次最多传输 4 字节的内存管道返回 `response`，响应头和响应体会被拆分到多次读取中
+    async fn exchange_with(response: Vec<u8>) -> Result<ProbeResponse, RpcError> {
+        let (client, server) = tokio::io::duplex(4);
+        let (mut server_read, mut server_write) = tokio::io::split(server);
+        tokio::spawn(async move {
+            let mut sink = Vec::new();
+            let _ = server_read.read_to_end(&mut sink).await;
+        });
+        tokio::spawn(async move {
+            let _ = server_write.write_all(&response).await;
+        });
+        exchange(client, b"POST / HTTP/1.1\r\n\r\n", b"{}", PhaseTimings::default()).await
+    }
+
+    #[tokio::test]
+    async fn reads_response_split_across_reads() {
+        let response = exchange_with(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"hello");
+    }
+
+    #[tokio::test]
+    async fn oversized_header_is_an_error() {
+        let mut response = b"HTTP/1.1 200 OK\r\n".to_vec();
+        response.extend(std::iter::repeat_n(b'x', MAX_HEADER_SIZE + 1));
+        assert!(exchange_with(response).await.is_err());
+    }
+
+    #[test]
+    fn decodes_chunks_split_across_reads() {
+        let data = b"5\r\nhello\r\n6;ext=1\r\n world\r\n0\r\n\r\n";
+        let mut decoder = ChunkedDecoder::default();
+        for end in 1..data.len() {
+            assert!(!decoder.decode(&data[..end]).unwrap());
+        }
+        assert!(decoder.decode(data).unwrap());
+        assert_eq!(decoder.decoded, b"hello world");
+    }
+
+    #[test]
+    fn malformed_size_line_is_an_error() {
+        assert!(ChunkedDecoder::default().decode(b"zz\r\nhello\r\n").is_err());
+        assert!(ChunkedDecoder::default().decode(b"\xff\r\nhello\r\n").is_err());
+        assert!(ChunkedDecoder::default().decode(&[b'1'; MAX_CHUNK_SIZE_LINE + 1]).is_err());
+    }
+
+    #[test]
+    fn chunk_without_trailing_crlf_is_an_error() {
+        assert!(ChunkedDecoder::default().decode(b"5\r\nhelloXX0\r\n\r\n").is_err());
+    }
+}
@@ -0,0 +1,505 @@
+use crate::chains::{Chain, ConnectionType};
+use crate::load::{self, LoadStats, Stage};
+use crate::methods::{self, RpcMethod};
+use crate::rpc::{self, Config, RpcManager};
+use anyhow::Result;
+use futures::future;
+use log::{info, warn};
+use prettytable::{format, Cell, Row, Table};
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// 阶梯负载测试参数
+#[derive(Debug, Clone)]
+pub struct RampConfig {
+    /// 依次执行的负载阶段，负载逐级升高
+    pub stages: Vec<Stage>,
+    /// 每个阶段的时长
+    pub stage_duration: Duration,
+    /// 速率阶段中每个端点同时在途的请求数上限
+    pub max_in_flight: usize,
+    /// 判定拐点的阈值
+    pub thresholds: KneeThresholds,
+}
+
+/// 拐点判定阈值，任一条件成立即视为端点已饱和
+#[derive(Debug, Clone)]
+pub struct KneeThresholds {
+    /// P99 延迟上限（毫秒）
+    pub max_p99_ms: Option<f64>,
+    /// P99 延迟相对第一个阶段的最大倍数
+    pub max_p99_factor: f64,
+    /// 错误率上限（0-1）
+    pub max_error_rate: f64,
+    /// 速率阶段中实际完成速率相对目标速率的最低比例（0-1）
+    pub min_throughput_ratio: f64,
+}
+
+impl KneeThresholds {
+    /// 检查一个阶段是否越过阈值，返回原因
+    fn check(&self, stage: Stage, stats: &LoadStats, baseline_p99: f64) -> Option<String> {
+        if stats.error_rate() > self.max_error_rate {
+            return Some(format!(
+                "错误率 {:.2}% 超过 {:.2}%",
+                stats.error_rate() * 100.0,
+                self.max_error_rate * 100.0
+            ));
+        }
+        if let Some(max_p99_ms) = self.max_p99_ms {
+            if stats.latency.p99 > max_p99_ms {
+                return Some(format!("P99 {:.2}ms 超过 {:.2}ms", stats.latency.p99, max_p99_ms));
+            }
+        }
+        if baseline_p99 > 0.0 && stats.latency.p99 > baseline_p99 * self.max_p99_factor {
+            return Some(format!(
+                "P99 {:.2}ms 超过第一个阶段的 {} 倍 ({:.2}ms)",
+                stats.latency.p99, self.max_p99_factor, baseline_p99
+            ));
+        }
+        if let Some(target_rps) = stage.target_rps() {
+            if stats.achieved_rps < target_rps * self.min_throughput_ratio {
+                return Some(format!("实际速率 {:.1}/秒 低于目标 {}/秒", stats.achieved_rps, target_rps));
+            }
+        }
+        None
+    }
+}
+
+/// 一个阶段的汇总结果
+#[derive(Debug, Clone)]
+pub struct StageStats {
+    pub stage: Stage,
+    /// 该阶段所有方法的汇总
+    pub stats: LoadStats,
+    /// 是否在该阶段越过阈值
+    pub knee: bool,
+}
+
+/// 一个端点的阶梯负载测试结果
+#[derive(Debug, Clone)]
+pub struct RampResult {
+    pub chain: String,
+    pub endpoint: String,
+    pub stages: Vec<StageStats>,
+    /// 越过阈值的原因，未越过时为 None
+    pub knee_reason: Option<String>,
+    pub proxy: String,
+}
+
+impl RampResult {
+    /// 越过阈值之前的最后一个阶段
+    pub fn last_healthy(&self) -> Option<&StageStats> {
+        self.stages.iter().take_while(|s| !s.knee).last()
+    }
+
+    /// 端点容量：越过阈值之前最后一个阶段的实际完成速率（请求/秒）
+    pub fn capacity_rps(&self) -> Option<f64> {
+        self.last_healthy().map(|s| s.stats.achieved_rps)
+    }
+
+    /// 越过阈值的阶段
+    pub fn knee_stage(&self) -> Option<&StageStats> {
+        self.stages.iter().find(|s| s.knee)
+    }
+}
+
+/// 阶梯负载测试：对每个端点逐级升高负载，找到延迟或错误率越过阈值的拐点
+///
+/// 每个阶段的结果与 `--load-rate` 负载测试相同；越过阈值后停止该端点后续的阶段。
+/// 各端点并行测试（受 `max_concurrent_requests` 限制），WebSocket 端点会被跳过。
+pub async fn run_ramp_test(
+    chains: &[Chain],
+    methods: &[RpcMethod],
+    ramp: &RampConfig,
+    config: &Config,
+) -> Result<Vec<RampResult>> {
+    let http_chains: Vec<&Chain> = chains.iter().filter(|c| c.connection_type.is_http()).collect();
+    for chain in chains.iter().filter(|c| c.connection_type == ConnectionType::WebSocket) {
+        warn!("阶梯负载测试不支持WebSocket端点，跳过: {}", chain.name);
+    }
+
+    let managers = http_chains
+        .iter()
+        .map(|chain| RpcManager::new(config.clone(), std::slice::from_ref(*chain)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let engine_methods = methods::get_engine_methods();
+    let semaphore = Semaphore::new(config.max_concurrent_requests.max(1));
+
+    info!(
+        "开始阶梯负载测试: {} 个端点, {} 个阶段, 每个阶段 {} 秒",
+        http_chains.len(),
+        ramp.stages.len(),
+        ramp.stage_duration.as_secs()
+    );
+
+    let results = future::join_all(http_chains.iter().zip(&managers).map(|(chain, manager)| {
+        let chain_methods = rpc::methods_for_chain(chain, methods, &engine_methods);
+        let semaphore = &semaphore;
+        async move {
+            let _permit = semaphore.acquire().await.expect("信号量不会被关闭");
+            ramp_endpoint(manager, chain, &chain_methods, ramp, config).await
+        }
+    }))
+    .await;
+
+    Ok(results)
+}
+
+/// 在一个端点上逐级执行负载阶段
+async fn ramp_endpoint(
+    manager: &RpcManager,
+    chain: &Chain,
+    chain_methods: &[&RpcMethod],
+    ramp: &RampConfig,
+    config: &Config,
+) -> RampResult {
+    let mut result = RampResult {
+        chain: chain.name.clone(),
+        endpoint: chain.rpc_url.clone(),
+        stages: Vec::new(),
+        knee_reason: None,
+        proxy: chain.proxy_label(),
+    };
+    if chain_methods.is_empty() {
+        println!("[{}] 没有可测试的方法，跳过阶梯负载测试", chain.name);
+        return result;
+    }
+
+    let mut baseline_p99 = None;
+    for (i, &stage) in ramp.stages.iter().enumerate() {
+        let duration = load::stage_duration(ramp.stage_duration, config);
+        if duration.is_zero() {
+            println!("[{}] 已到达测试截止时间，剩余的阶段未测试", chain.name);
+            break;
+        }
+
+        let run = load::run_stage(manager, chain, chain_methods, stage, duration, ramp.max_in_flight, config).await;
+        let stats = load::summarize(chain, chain_methods, stage, &run).pop().expect("汇总行总是存在");
+        let baseline = *baseline_p99.get_or_insert(stats.latency.p99);
+        let knee_reason = ramp.thresholds.check(stage, &stats, baseline);
+
+        println!(
+            "[{}] 阶段[{}/{}] {}: 实际 {:.1} 请求/秒, 错误率 {:.2}%, P50 {:.2}ms, P99 {:.2}ms{}",
+            chain.name,
+            i + 1,
+            ramp.stages.len(),
+            stage.label(),
+            stats.achieved_rps,
+            stats.error_rate() * 100.0,
+            stats.latency.p50,
+            stats.latency.p99,
+            knee_reason.as_ref().map(|r| format!(" - 拐点: {}", r)).unwrap_or_default()
+        );
+
+        result.stages.push(StageStats {
+            stage,
+            stats,
+            knee: knee_reason.is_some(),
+        });
+        if knee_reason.is_some() {
+            result.knee_reason = knee_reason;
+            break;
+        }
+    }
+
+    result
+}
+
+/// 解析逗号分隔的阶段列表，阶段必须逐级升高
+pub fn parse_stages(stages: &str, concurrency: bool) -> Result<Vec<Stage>> {
+    let stages = stages
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            if concurrency {
+                match s.parse::<usize>() {
+                    Ok(workers) if workers > 0 => Ok(Stage::Concurrency(workers)),
+                    _ => anyhow::bail!("无效的并发数: {} (需要正整数)", s),
+                }
+            } else {
+                match s.parse::<f64>() {
+                    Ok(rate) if rate.is_finite() && rate > 0.0 => Ok(Stage::Rate(rate)),
+                    _ => anyhow::bail!("无效的请求速率: {} (需要大于0的数字)", s),
+                }
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if stages.is_empty() {
+        anyhow::bail!("阶梯负载测试至少需要一个阶段");
+    }
+    let load = |stage: &Stage| match stage {
+        Stage::Rate(rate) => *rate,
+        Stage::Concurrency(workers) => *workers as f64,
+    };
+    if stages.windows(2).any(|w| load(&w[1]) <= load(&w[0])) {
+        anyhow::bail!("阶梯负载测试的阶段必须逐级升高");
+    }
+    Ok(stages)
+}
+
+/// 将各阶段统计写入 CSV 文件
+pub fn write_stages_to_csv(results: &[RampResult], output_path: &Path) -> Result<()> {
+    let file = File::create(output_path)?;
+    let mut wtr = csv::Writer::from_writer(file);
+
+    wtr.write_record([
+        "chain",
+        "endpoint",
+        "stage",
+        "target_rps",
+        "concurrency",
+        "achieved_rps",
+        "success_count",
+        "error_count",
+        "unsent",
+        "error_percent",
+        "p50_latency_ms",
+        "p90_latency_ms",
+        "p99_latency_ms",
+        "max_latency_ms",
+        "service_p99_latency_ms",
+        "knee",
+        "proxy",
+    ])?;
+
+    for result in results {
+        for (i, stage) in result.stages.iter().enumerate() {
+            let concurrency = match stage.stage {
+                Stage::Concurrency(workers) => workers.to_string(),
+                Stage::Rate(_) => String::new(),
+            };
+            let stats = &stage.stats;
+            wtr.write_record([
+                &result.chain,
+                &result.endpoint,
+                &(i + 1).to_string(),
+                &stage.stage.target_rps().map(|r| format!("{:.2}", r)).unwrap_or_default(),
+                &concurrency,
+                &format!("{:.2}", stats.achieved_rps),
+                &stats.success_count.to_string(),
+                &stats.error_count.to_string(),
+                &stats.unsent.to_string(),
+                &format!("{:.2}", stats.error_rate() * 100.0),
+                &format!("{:.2}", stats.latency.p50),
+                &format!("{:.2}", stats.latency.p90),
+                &format!("{:.2}", stats.latency.p99),
+                &format!("{:.2}", stats.latency.max),
+                &format!("{:.2}", stats.service_latency.p99),
+                &stage.knee.to_string(),
+                &result.proxy,
+            ])?;
+        }
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// 将各端点容量写入 CSV 文件
+pub fn write_capacity_to_csv(results: &[RampResult], output_path: &Path) -> Result<()> {
+    let file = File::create(output_path)?;
+    let mut wtr = csv::Writer::from_writer(file);
+
+    wtr.write_record([
+        "chain",
+        "endpoint",
+        "capacity_rps",
+        "capacity_stage",
+        "knee_stage",
+        "knee_reason",
+        "proxy",
+    ])?;
+
+    for result in results {
+        wtr.write_record([
+            &result.chain,
+            &result.endpoint,
+            &result.capacity_rps().map(|r| format!("{:.2}", r)).unwrap_or_default(),
+            &result.last_healthy().map(|s| s.stage.label()).unwrap_or_default(),
+            &result.knee_stage().map(|s| s.stage.label()).unwrap_or_default(),
+            result.knee_reason.as_deref().unwrap_or(""),
+            &result.proxy,
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// 在控制台中打印各阶段统计
+pub fn print_stages(results: &[RampResult]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("阶段"),
+        Cell::new("负载"),
+        Cell::new("实际/秒"),
+        Cell::new("错误率"),
+        Cell::new("P50(ms)"),
+        Cell::new("P90(ms)"),
+        Cell::new("P99(ms)"),
+        Cell::new("最大(ms)"),
+        Cell::new("拐点"),
+    ]));
+
+    for result in results {
+        for (i, stage) in result.stages.iter().enumerate() {
+            let stats = &stage.stats;
+            let knee = if stage.knee {
+                Cell::new("是").style_spec("Fg=Red")
+            } else {
+                Cell::new("")
+            };
+            table.add_row(Row::new(vec![
+                Cell::new(&result.chain),
+                Cell::new(&(i + 1).to_string()),
+                Cell::new(&stage.stage.label()),
+                Cell::new(&format!("{:.1}", stats.achieved_rps)),
+                Cell::new(&format!("{:.2}%", stats.error_rate() * 100.0)),
+                Cell::new(&format!("{:.2}", stats.latency.p50)),
+                Cell::new(&format!("{:.2}", stats.latency.p90)),
+                Cell::new(&format!("{:.2}", stats.latency.p99)),
+                Cell::new(&format!("{:.2}", stats.latency.max)),
+                knee,
+            ]));
+        }
+    }
+
+    table.printstd();
+}
+
+/// 在控制台中打印各端点容量
+pub fn print_capacity(results: &[RampResult]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("容量(请求/秒)"),
+        Cell::new("容量阶段"),
+        Cell::new("拐点阶段"),
+        Cell::new("拐点原因"),
+    ]));
+
+    for result in results {
+        // 所有阶段都未越过阈值时，容量只是下限
+        let capacity = match (result.capacity_rps(), &result.knee_reason) {
+            (Some(rps), Some(_)) => format!("{:.1}", rps),
+            (Some(rps), None) => format!(">= {:.1}", rps),
+            (None, _) => "-".to_string(),
+        };
+        let knee_reason = match (&result.knee_reason, result.stages.is_empty()) {
+            (Some(reason), _) => reason.clone(),
+            (None, false) => "所有阶段均未越过阈值".to_string(),
+            (None, true) => "未测试".to_string(),
+        };
+        table.add_row(Row::new(vec![
+            Cell::new(&result.chain),
+            Cell::new(&capacity),
+            Cell::new(&result.last_healthy().map(|s| s.stage.label()).unwrap_or_default()),
+            Cell::new(&result.knee_stage().map(|s| s.stage.label()).unwrap_or_default()),
+            Cell::new(&knee_reason),
+        ]));
+    }
+
+    table.printstd();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load::LatencyPercentiles;
+
+    fn thresholds() -> KneeThresholds {
+        KneeThresholds {
+            max_p99_ms: Some(500.0),
+            max_p99_factor: 3.0,
+            max_error_rate: 0.01,
+            min_throughput_ratio: 0.9,
+        }
+    }
+
+    /// 目标速率为 100/秒的阶段统计
+    fn stats(success_count: u64, error_count: u64, p99_ms: f64, achieved_rps: f64) -> LoadStats {
+        LoadStats {
+            chain: "ETH-HTTP".to_string(),
+            endpoint: "http://localhost:8545".to_string(),
+            method: "*".to_string(),
+            target_rps: 100.0,
+            scheduled: success_count + error_count,
+            sent: success_count + error_count,
+            success_count,
+            error_count,
+            unsent: 0,
+            achieved_rps,
+            latency: LatencyPercentiles {
+                p99: p99_ms,
+                ..LatencyPercentiles::default()
+            },
+            service_latency: LatencyPercentiles::default(),
+            proxy: String::new(),
+        }
+    }
+
+    #[test]
+    fn parses_rate_and_concurrency_stages() {
+        assert_eq!(
+            parse_stages("50, 100,200.5", false).unwrap(),
+            vec![Stage::Rate(50.0), Stage::Rate(100.0), Stage::Rate(200.5)]
+        );
+        assert_eq!(
+            parse_stages("1,4,16", true).unwrap(),
+            vec![Stage::Concurrency(1), Stage::Concurrency(4), Stage::Concurrency(16)]
+        );
+        // 并发数必须是整数
+        assert!(parse_stages("1.5,4", true).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_stages() {
+        assert!(parse_stages("", false).is_err());
+        assert!(parse_stages(" , ", false).is_err());
+        assert!(parse_stages("0,100", false).is_err());
+        assert!(parse_stages("0,4", true).is_err());
+        assert!(parse_stages("50,abc", false).is_err());
+        assert!(parse_stages("100,50", false).is_err());
+    }
+
+    #[test]
+    fn healthy_stage_is_not_a_knee() {
+        assert_eq!(thresholds().check(Stage::Rate(100.0), &stats(1000, 0, 100.0, 100.0), 50.0), None);
+    }
+
+    #[test]
+    fn error_rate_triggers_knee() {
+        let reason = thresholds().check(Stage::Rate(100.0), &stats(980, 20, 100.0, 100.0), 50.0).unwrap();
+        assert!(reason.contains("错误率"), "{}", reason);
+    }
+
+    #[test]
+    fn absolute_p99_triggers_knee() {
+        let reason = thresholds().check(Stage::Rate(100.0), &stats(1000, 0, 600.0, 100.0), 300.0).unwrap();
+        assert!(reason.contains("超过 500.00ms"), "{}", reason);
+    }
+
+    #[test]
+    fn p99_factor_triggers_knee() {
+        let reason = thresholds().check(Stage::Rate(100.0), &stats(1000, 0, 400.0, 100.0), 100.0).unwrap();
+        assert!(reason.contains("3 倍"), "{}", reason);
+        // 没有基线时不按倍数判定
+        assert_eq!(thresholds().check(Stage::Rate(100.0), &stats(1000, 0, 400.0, 100.0), 0.0), None);
+    }
+
+    #[test]
+    fn throughput_ratio_triggers_knee_for_rate_stages_only() {
+        let reason = thresholds().check(Stage::Rate(100.0), &stats(1000, 0, 100.0, 80.0), 50.0).unwrap();
+        assert!(reason.contains("实际速率"), "{}", reason);
+        assert_eq!(thresholds().check(Stage::Concurrency(8), &stats(1000, 0, 100.0, 80.0), 50.0), None);
+    }
+}
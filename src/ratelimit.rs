@@ -0,0 +1,110 @@
+use crate::rpc::RpcError;
+use serde_json::Value;
+use std::time::Duration;
+
+/// 遵从 Retry-After 时单次等待的上限，避免节点返回过大的值使测试停滞
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// 各服务商表示限流的 JSON-RPC 错误码
+///
+/// -32005 为 EIP-1474 的 "Limit exceeded"（Infura 等），-32007 为 QuickNode 的请求数上限，
+/// -32090 为部分节点服务的请求频率限制，429 为直接沿用 HTTP 状态码的实现（Alchemy 等）。
+const RATE_LIMIT_CODES: [i64; 4] = [-32005, -32007, -32090, 429];
+
+/// 限流错误信息中的常见关键词（小写）
+const RATE_LIMIT_MESSAGES: [&str; 7] = [
+    "rate limit",
+    "rate-limit",
+    "ratelimit",
+    "too many requests",
+    "request limit",
+    "limit exceeded",
+    "exceeded the quota",
+];
+
+/// 按 HTTP 状态码判断是否被限流：429 总是视为限流，503 附带 Retry-After 时同样视为限流
+pub fn check_http_status(status: u16, retry_after: Option<&str>) -> Result<(), RpcError> {
+    if status == 429 || (status == 503 && retry_after.is_some()) {
+        return Err(RpcError::RateLimited {
+            message: format!("HTTP {}", status),
+            retry_after: retry_after.and_then(parse_retry_after),
+            http_status: Some(status),
+        });
+    }
+    Ok(())
+}
+
+/// 判断 JSON-RPC 错误对象是否表示限流
+pub fn is_rate_limit_error(error: &Value) -> bool {
+    if let Some(code) = error.get("code").and_then(|c| c.as_i64()) {
+        if RATE_LIMIT_CODES.contains(&code) {
+            return true;
+        }
+    }
+    let message = error
+        .get("message")
+        .and_then(|m| m.as_str())
+        .map(|m| m.to_lowercase())
+        .unwrap_or_default();
+    RATE_LIMIT_MESSAGES.iter().any(|keyword| message.contains(keyword))
+}
+
+/// 解析 Retry-After 头，支持秒数和 HTTP 日期两种格式
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.signed_duration_since(chrono::Utc::now());
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn retry_after_accepts_seconds_and_http_dates() {
+        assert_eq!(parse_retry_after(" 5 "), Some(Duration::from_secs(5)));
+        assert_eq!(parse_retry_after("0"), Some(Duration::ZERO));
+
+        let future = (chrono::Utc::now() + chrono::Duration::seconds(30)).to_rfc2822();
+        let delay = parse_retry_after(&future).unwrap();
+        assert!(delay > Duration::from_secs(25) && delay <= Duration::from_secs(30), "{:?}", delay);
+
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon"), None);
+        assert_eq!(parse_retry_after("-1"), None);
+    }
+
+    #[test]
+    fn rate_limit_codes_and_messages_are_recognized() {
+        for code in RATE_LIMIT_CODES {
+            assert!(is_rate_limit_error(&json!({ "code": code, "message": "error" })), "{}", code);
+        }
+        for message in ["Too Many Requests", "daily request count exceeded, request rate limited", "You have exceeded the quota"] {
+            assert!(is_rate_limit_error(&json!({ "code": -32000, "message": message })), "{}", message);
+        }
+        assert!(!is_rate_limit_error(&json!({ "code": -32601, "message": "the method does not exist" })));
+        assert!(!is_rate_limit_error(&json!({ "code": -32000 })));
+    }
+
+    #[test]
+    fn http_429_and_503_with_retry_after_are_rate_limited() {
+        assert!(check_http_status(200, None).is_ok());
+        assert!(check_http_status(503, None).is_ok());
+        match check_http_status(429, Some("2")) {
+            Err(RpcError::RateLimited { retry_after, http_status, .. }) => {
+                assert_eq!(retry_after, Some(Duration::from_secs(2)));
+                assert_eq!(http_status, Some(429));
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(matches!(
+            check_http_status(503, Some("1")),
+            Err(RpcError::RateLimited { http_status: Some(503), .. })
+        ));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::rpc::ErrorKind;
+use anyhow::Result;
+use rand::Rng;
+use std::time::Duration;
+
+/// 默认可重试的错误分类
+pub const DEFAULT_RETRYABLE: [ErrorKind; 5] = [
+    ErrorKind::Network,
+    ErrorKind::Timeout,
+    ErrorKind::WebSocket,
+    ErrorKind::HttpStatus,
+    ErrorKind::RateLimited,
+];
+
+/// 失败调用的重试策略
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// 每次调用的最大尝试次数（包括第一次），1 表示不重试
+    pub max_attempts: u32,
+    /// 第一次重试前的基础退避时间（毫秒），之后每次翻倍
+    pub initial_backoff_ms: u64,
+    /// 退避时间上限（毫秒）
+    pub max_backoff_ms: u64,
+    /// 可重试的错误分类
+    pub retryable: Vec<ErrorKind>,
+}
+
+impl RetryPolicy {
+    /// 是否启用了重试
+    pub fn enabled(&self) -> bool {
+        self.max_attempts > 1
+    }
+
+    /// 是否应在第 `attempt` 次尝试以 `kind` 失败后重试
+    pub fn should_retry(&self, attempt: u32, kind: ErrorKind) -> bool {
+        attempt < self.max_attempts && self.retryable.contains(&kind)
+    }
+
+    /// 第 `attempt` 次尝试失败后的退避时间
+    ///
+    /// 指数退避并使用 full jitter：在 0 到当前退避上限之间均匀随机取值，避免多个客户端同时重试。
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        Duration::from_millis(rand::thread_rng().gen_range(0..=self.backoff_ceiling_ms(attempt)))
+    }
+
+    /// 第 `attempt` 次尝试失败后退避时间的上限（毫秒）
+    fn backoff_ceiling_ms(&self, attempt: u32) -> u64 {
+        let factor = 1u64 << attempt.saturating_sub(1).min(16);
+        self.initial_backoff_ms.saturating_mul(factor).min(self.max_backoff_ms)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 2_000,
+            retryable: DEFAULT_RETRYABLE.to_vec(),
+        }
+    }
+}
+
+/// 解析逗号分隔的可重试错误分类
+pub fn parse_retryable(kinds: &str) -> Result<Vec<ErrorKind>> {
+    kinds
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(initial_backoff_ms: u64, max_backoff_ms: u64) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms,
+            max_backoff_ms,
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_ceiling() {
+        let policy = policy(100, 2_000);
+        let ceilings: Vec<u64> = (1..=7).map(|attempt| policy.backoff_ceiling_ms(attempt)).collect();
+        assert_eq!(ceilings, [100, 200, 400, 800, 1_600, 2_000, 2_000]);
+        for attempt in 1..=7 {
+            assert!(policy.backoff(attempt) <= Duration::from_millis(2_000));
+        }
+    }
+
+    #[test]
+    fn backoff_saturates_at_large_attempts() {
+        assert_eq!(policy(100, 2_000).backoff_ceiling_ms(u32::MAX), 2_000);
+        assert_eq!(policy(u64::MAX / 2, u64::MAX).backoff_ceiling_ms(40), u64::MAX);
+        assert_eq!(policy(100, u64::MAX).backoff_ceiling_ms(u32::MAX), 100 << 16);
+        assert_eq!(policy(0, 2_000).backoff(5), Duration::ZERO);
+    }
+
+    #[test]
+    fn should_retry_respects_attempts_and_kinds() {
+        let policy = RetryPolicy {
+            retryable: vec![ErrorKind::Timeout],
+            ..policy(100, 2_000)
+        };
+        assert!(policy.should_retry(1, ErrorKind::Timeout));
+        assert!(policy.should_retry(2, ErrorKind::Timeout));
+        assert!(!policy.should_retry(3, ErrorKind::Timeout));
+        assert!(!policy.should_retry(1, ErrorKind::Network));
+    }
+
+    #[test]
+    fn retryable_kinds_are_parsed() {
+        assert_eq!(
+            parse_retryable(" Timeout, network,,").unwrap(),
+            [ErrorKind::Timeout, ErrorKind::Network]
+        );
+        assert!(parse_retryable("").unwrap().is_empty());
+        assert!(parse_retryable("timeout,bogus").is_err());
+    }
+}
@@ -1,24 +1,197 @@
 use crate::chains::{Chain, ConnectionType};
-use crate::methods::RpcMethod;
-use anyhow::{Context, Result, anyhow};
+use crate::compression::{self, Compression};
+use crate::methods::{self, RpcMethod};
+use crate::net;
+use crate::pacing::TokenBucket;
+use crate::probe;
+use crate::ratelimit;
+use crate::retry::RetryPolicy;
+use crate::stats::StatsCollector;
+use crate::tls;
+use anyhow::Result;
 use chrono::Utc;
-use futures::future::{self, Either};
-use futures::{pin_mut, SinkExt, StreamExt};
+use futures::{future, SinkExt, StreamExt};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 use reqwest::Client;
 use serde_json::{json, Value};
 use std::time::{Duration, Instant};
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message, WebSocketStream, MaybeTlsStream};
+use tokio_tungstenite::tungstenite::{self, protocol::{Message, WebSocketConfig}};
+use tokio_tungstenite::{client_async_with_config, WebSocketStream, MaybeTlsStream};
 use url::Url;
 use tokio::net::TcpStream;
-use std::collections::HashMap;
-use log::{debug, info, warn, error};
+use tokio::sync::{OnceCell, Semaphore};
+use std::collections::{HashMap, HashSet};
+use log::{debug, info, log, warn, Level};
+
+/// 连接复用模式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionMode {
+    /// 复用连接池中的连接（默认）
+    Warm,
+    /// 每次请求都建立新连接，包含DNS、TCP和TLS建连耗时
+    Cold,
+    /// 每轮分别执行一次冷连接和热连接请求
+    Both,
+}
+
+impl ConnectionMode {
+    /// 是否执行热连接请求
+    pub fn includes_warm(&self) -> bool {
+        matches!(self, ConnectionMode::Warm | ConnectionMode::Both)
+    }
+
+    /// 是否执行冷连接请求
+    pub fn includes_cold(&self) -> bool {
+        matches!(self, ConnectionMode::Cold | ConnectionMode::Both)
+    }
+}
+
+impl std::str::FromStr for ConnectionMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "warm" => Ok(ConnectionMode::Warm),
+            "cold" => Ok(ConnectionMode::Cold),
+            "both" => Ok(ConnectionMode::Both),
+            _ => anyhow::bail!("无效的连接模式: {}。支持的模式: warm, cold, both", s),
+        }
+    }
+}
+
+/// 延迟测试中各端点、各方法请求的调度方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Schedule {
+    /// 各端点独立地逐个方法测试，端点之间并行（默认）
+    Endpoint,
+    /// 所有端点交错执行：每一轮依次测试每个方法，同一方法在各端点上相邻执行
+    RoundRobin,
+    /// 所有端点交错执行：每一轮以随机顺序测试所有端点和方法的组合
+    Random,
+    /// 所有端点交错执行：逐个方法测试，一个方法的所有轮次完成后再测试下一个方法
+    Method,
+}
+
+impl Schedule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Schedule::Endpoint => "endpoint",
+            Schedule::RoundRobin => "round-robin",
+            Schedule::Random => "random",
+            Schedule::Method => "method",
+        }
+    }
+
+    /// 是否在同一个任务中交错测试所有端点
+    pub fn is_interleaved(&self) -> bool {
+        *self != Schedule::Endpoint
+    }
+}
+
+impl std::str::FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "endpoint" => Ok(Schedule::Endpoint),
+            "round-robin" => Ok(Schedule::RoundRobin),
+            "random" => Ok(Schedule::Random),
+            "method" => Ok(Schedule::Method),
+            _ => anyhow::bail!("无效的调度方式: {}。支持的方式: endpoint, round-robin, random, method", s),
+        }
+    }
+}
 
 /// 配置常量
+#[derive(Debug, Clone)]
 pub struct Config {
+    /// HTTP 请求超时（秒）
     pub http_timeout_secs: u64,
+    /// WebSocket 请求超时（秒）
     pub ws_timeout_secs: u64,
+    /// 同一方法两轮请求之间的间隔（毫秒）
     pub request_delay_ms: u64,
+    /// 在间隔上附加的随机抖动上限（毫秒）
+    pub request_jitter_ms: u64,
+    /// 端点限速的令牌桶容量，即最多连续发出的请求数
+    pub rate_limit_burst: u32,
+    /// 正式测试前每个方法的预热请求次数，预热请求不计入延迟统计
+    pub warmup: usize,
+    /// 同时进行测试的端点数上限，每个端点的请求依次发送；交错调度时不使用
     pub max_concurrent_requests: usize,
+    /// 延迟测试的调度方式
+    pub schedule: Schedule,
+    /// 随机调度的种子，相同的种子产生相同的请求顺序
+    pub schedule_seed: u64,
+    /// 按方法名称覆盖的请求超时（秒），对 HTTP 和 WebSocket 同时生效
+    pub method_timeouts: HashMap<String, u64>,
+    /// 整个测试的截止时间，到达后不再发出新的请求
+    pub deadline: Option<Instant>,
+    /// WebSocket 断线后的最大重连次数
+    pub ws_reconnect_attempts: u32,
+    /// 重连初始退避时间（毫秒），每次失败后翻倍
+    pub ws_reconnect_backoff_ms: u64,
+    /// 重连退避时间上限（毫秒）
+    pub ws_reconnect_max_backoff_ms: u64,
+    /// WebSocket 单条消息的最大字节数
+    pub ws_max_message_size: usize,
+    /// WebSocket 单个帧的最大字节数
+    pub ws_max_frame_size: usize,
+    /// 连接复用模式
+    pub connection_mode: ConnectionMode,
+    /// HTTP 响应压缩协商方式
+    pub compression: Compression,
+    /// 是否对每个 HTTP 端点对比不压缩与各压缩编码的响应
+    pub compare_compression: bool,
+    /// 被限流时是否按 Retry-After 等待后再发送下一个请求
+    pub honor_retry_after: bool,
+    /// 失败调用的重试策略
+    pub retry: RetryPolicy,
+}
+
+impl Config {
+    /// 某个方法在某种连接上的请求超时（秒）
+    pub fn timeout_secs(&self, method: &str, connection_type: &ConnectionType) -> u64 {
+        match self.method_timeouts.get(method) {
+            Some(&secs) => secs,
+            None if *connection_type == ConnectionType::WebSocket => self.ws_timeout_secs,
+            None => self.http_timeout_secs,
+        }
+    }
+
+    /// 两轮请求之间的间隔：固定间隔加上 0 到抖动上限之间的均匀随机值
+    pub fn request_pause(&self) -> Duration {
+        let jitter = rand::thread_rng().gen_range(0..=self.request_jitter_ms);
+        Duration::from_millis(self.request_delay_ms.saturating_add(jitter))
+    }
+
+    /// 调度方式的说明，随机调度附带种子，记录在报告中以便复现
+    pub fn schedule_label(&self) -> String {
+        match self.schedule {
+            Schedule::Random => format!("random (seed={})", self.schedule_seed),
+            schedule => schedule.name().to_string(),
+        }
+    }
+
+    /// 距截止时间的剩余时间，没有截止时间时为 None
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// 第 `attempt` 次重连前的退避时间（第一次立即重连）
+    pub fn reconnect_backoff(&self, attempt: u32) -> Duration {
+        if attempt <= 1 {
+            return Duration::ZERO;
+        }
+        let factor = 1u64 << (attempt - 2).min(16);
+        Duration::from_millis(
+            self.ws_reconnect_backoff_ms
+                .saturating_mul(factor)
+                .min(self.ws_reconnect_max_backoff_ms),
+        )
+    }
 }
 
 impl Default for Config {
@@ -27,12 +200,30 @@ impl Default for Config {
             http_timeout_secs: 10,
             ws_timeout_secs: 15,
             request_delay_ms: 100,
+            request_jitter_ms: 0,
+            rate_limit_burst: 1,
+            warmup: 0,
             max_concurrent_requests: 10,
+            schedule: Schedule::Endpoint,
+            schedule_seed: 0,
+            method_timeouts: HashMap::new(),
+            deadline: None,
+            ws_reconnect_attempts: 5,
+            ws_reconnect_backoff_ms: 500,
+            ws_reconnect_max_backoff_ms: 10_000,
+            ws_max_message_size: 64 << 20,
+            ws_max_frame_size: 16 << 20,
+            connection_mode: ConnectionMode::Warm,
+            compression: Compression::Identity,
+            compare_compression: false,
+            honor_retry_after: false,
+            retry: RetryPolicy::default(),
         }
     }
 }
 
 /// 自定义错误类型
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, thiserror::Error)]
 pub enum RpcError {
     #[error("网络连接错误: {0}")]
@@ -45,6 +236,257 @@ pub enum RpcError {
     TimeoutError(String),
     #[error("配置错误: {0}")]
     ConfigError(String),
+    #[error("TLS错误: {0}")]
+    TlsError(String),
+    #[error("HTTP错误 {status}: {message}")]
+    HttpStatusError {
+        status: u16,
+        /// 响应体开头部分
+        message: String,
+    },
+    #[error("节点返回错误{}: {message}", format_error_code(*.code))]
+    ErrorResponse {
+        /// JSON-RPC `error.code`
+        code: Option<i64>,
+        message: String,
+        /// JSON-RPC `error.data`
+        data: Option<Value>,
+    },
+    #[error("结果校验失败: {0}")]
+    InvalidResult(String),
+    #[error("请求被限流: {message}")]
+    RateLimited {
+        message: String,
+        /// 节点通过 Retry-After 要求的等待时间
+        retry_after: Option<Duration>,
+        /// 按 HTTP 状态码判断为限流时的状态码
+        http_status: Option<u16>,
+    },
+}
+
+fn format_error_code(code: Option<i64>) -> String {
+    code.map(|c| format!(" ({})", c)).unwrap_or_default()
+}
+
+/// 错误的分类，用于统计和导出
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorKind {
+    /// DNS、TCP 等网络错误
+    Network,
+    /// 请求超时
+    Timeout,
+    /// TLS 握手失败或证书指纹不匹配
+    Tls,
+    /// WebSocket 连接错误
+    WebSocket,
+    /// 非 2xx 且响应体不是 JSON-RPC 错误的 HTTP 响应
+    HttpStatus,
+    /// 响应体为空或无法解析
+    InvalidResponse,
+    /// 响应没有错误，但 `result` 不符合方法的校验规则
+    InvalidResult,
+    /// 被限流
+    RateLimited,
+    /// -32700 Parse error
+    ParseError,
+    /// -32600 Invalid request
+    InvalidRequest,
+    /// -32601 Method not found
+    MethodNotFound,
+    /// -32602 Invalid params
+    InvalidParams,
+    /// -32603 Internal error
+    InternalError,
+    /// 3 执行回滚 (eth_call / eth_estimateGas)
+    ExecutionReverted,
+    /// -32000 到 -32099 的节点自定义错误
+    ServerError,
+    /// 其他错误码或没有错误码的错误
+    Other,
+    /// 配置错误
+    Config,
+}
+
+impl ErrorKind {
+    /// 所有错误分类
+    pub const ALL: [ErrorKind; 17] = [
+        ErrorKind::Network,
+        ErrorKind::Timeout,
+        ErrorKind::Tls,
+        ErrorKind::WebSocket,
+        ErrorKind::HttpStatus,
+        ErrorKind::InvalidResponse,
+        ErrorKind::InvalidResult,
+        ErrorKind::RateLimited,
+        ErrorKind::ParseError,
+        ErrorKind::InvalidRequest,
+        ErrorKind::MethodNotFound,
+        ErrorKind::InvalidParams,
+        ErrorKind::InternalError,
+        ErrorKind::ExecutionReverted,
+        ErrorKind::ServerError,
+        ErrorKind::Other,
+        ErrorKind::Config,
+    ];
+
+    /// 报告中使用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::Network => "network",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Tls => "tls",
+            ErrorKind::WebSocket => "websocket",
+            ErrorKind::HttpStatus => "http_status",
+            ErrorKind::InvalidResponse => "invalid_response",
+            ErrorKind::InvalidResult => "invalid_result",
+            ErrorKind::RateLimited => "rate_limited",
+            ErrorKind::ParseError => "parse_error",
+            ErrorKind::InvalidRequest => "invalid_request",
+            ErrorKind::MethodNotFound => "method_not_found",
+            ErrorKind::InvalidParams => "invalid_params",
+            ErrorKind::InternalError => "internal_error",
+            ErrorKind::ExecutionReverted => "execution_reverted",
+            ErrorKind::ServerError => "server_error",
+            ErrorKind::Other => "other",
+            ErrorKind::Config => "config",
+        }
+    }
+
+    /// 按 JSON-RPC 错误码分类
+    pub fn from_code(code: Option<i64>) -> Self {
+        match code {
+            Some(-32700) => ErrorKind::ParseError,
+            Some(-32600) => ErrorKind::InvalidRequest,
+            Some(-32601) => ErrorKind::MethodNotFound,
+            Some(-32602) => ErrorKind::InvalidParams,
+            Some(-32603) => ErrorKind::InternalError,
+            Some(3) => ErrorKind::ExecutionReverted,
+            Some(-32099..=-32000) => ErrorKind::ServerError,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+impl std::str::FromStr for ErrorKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        ErrorKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s.trim().to_lowercase())
+            .ok_or_else(|| anyhow::anyhow!(
+                "无效的错误类型: {}。支持的类型: {}",
+                s,
+                ErrorKind::ALL.iter().map(|k| k.name()).collect::<Vec<_>>().join(", ")
+            ))
+    }
+}
+
+impl RpcError {
+    /// 是否为限流错误
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(self, RpcError::RateLimited { .. })
+    }
+
+    /// 限流错误附带的 Retry-After 等待时间
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            RpcError::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// 错误分类
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            RpcError::NetworkError(_) => ErrorKind::Network,
+            RpcError::JsonRpcError(_) => ErrorKind::InvalidResponse,
+            RpcError::InvalidResult(_) => ErrorKind::InvalidResult,
+            RpcError::WebSocketError(_) => ErrorKind::WebSocket,
+            RpcError::TimeoutError(_) => ErrorKind::Timeout,
+            RpcError::ConfigError(_) => ErrorKind::Config,
+            RpcError::TlsError(_) => ErrorKind::Tls,
+            RpcError::HttpStatusError { .. } => ErrorKind::HttpStatus,
+            RpcError::ErrorResponse { code, .. } => ErrorKind::from_code(*code),
+            RpcError::RateLimited { .. } => ErrorKind::RateLimited,
+        }
+    }
+
+    /// 错误附带的 HTTP 状态码
+    pub fn http_status(&self) -> Option<u16> {
+        match self {
+            RpcError::HttpStatusError { status, .. } => Some(*status),
+            RpcError::RateLimited { http_status, .. } => *http_status,
+            _ => None,
+        }
+    }
+}
+
+/// 单次请求各阶段的耗时（毫秒），未经历或无法测量的阶段为 None
+///
+/// 复用连接的请求没有 DNS、TCP 和 TLS 阶段；通过 reqwest 发送的请求无法单独测量写请求阶段，
+/// 其首字节时间包含写请求的耗时。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PhaseTimings {
+    /// DNS 解析
+    pub dns_ms: Option<f64>,
+    /// TCP 连接
+    pub connect_ms: Option<f64>,
+    /// TLS 握手
+    pub tls_ms: Option<f64>,
+    /// 写入请求
+    pub request_write_ms: Option<f64>,
+    /// 从请求发出到收到首字节 (TTFB)
+    pub ttfb_ms: Option<f64>,
+    /// 从首字节到响应体接收完毕
+    pub body_ms: Option<f64>,
+}
+
+/// 单次请求的传输数据量
+#[derive(Debug, Clone, Default)]
+pub struct Transfer {
+    /// 请求体字节数
+    pub request_bytes: usize,
+    /// 线路上收到的响应体字节数（压缩后）
+    pub response_bytes: usize,
+    /// 解压后的响应体字节数
+    pub decoded_bytes: usize,
+    /// 响应的 Content-Encoding，未压缩时为 None
+    pub content_encoding: Option<String>,
+}
+
+impl Transfer {
+    /// 未压缩消息的传输数据量
+    fn uncompressed(request_bytes: usize, response_bytes: usize) -> Self {
+        Self {
+            request_bytes,
+            response_bytes,
+            decoded_bytes: response_bytes,
+            content_encoding: None,
+        }
+    }
+}
+
+/// 一次请求收到的 JSON-RPC 响应
+#[derive(Debug, Clone)]
+pub struct RpcResponse {
+    /// 请求延迟（毫秒），包含响应体下载
+    pub latency_ms: f64,
+    /// 响应体
+    pub body: Value,
+    /// 各阶段耗时
+    pub phases: PhaseTimings,
+    /// 传输数据量
+    pub transfer: Transfer,
+    /// HTTP 状态码，WebSocket 请求为 None
+    pub http_status: Option<u16>,
+}
+
+impl RpcResponse {
+    /// 响应中的错误，成功的响应返回 None
+    pub fn error(&self) -> Option<RpcError> {
+        rpc_error(&self.body)
+    }
 }
 
 /// RPC 调用的结果
@@ -54,6 +496,8 @@ pub struct RpcResult {
     pub chain: String,
     /// 端点URL
     pub endpoint: String,
+    /// 端点的连接类型
+    pub connection_type: ConnectionType,
     /// 方法名称
     pub method: String,
     /// 调用是否成功
@@ -62,13 +506,89 @@ pub struct RpcResult {
     pub latency_ms: f64,
     /// 如果调用失败，则包含错误信息
     pub error: Option<String>,
+    /// 是否在全新连接上发出（冷连接）
+    pub cold: bool,
+    /// 各阶段耗时
+    pub phases: PhaseTimings,
+    /// 请求使用的压缩协商方式
+    pub compression: Compression,
+    /// 传输数据量
+    pub transfer: Transfer,
+    /// 使用的代理（隐去密码），直连时为空
+    pub proxy: String,
+    /// 失败调用的错误分类
+    pub error_kind: Option<ErrorKind>,
+    /// HTTP 状态码，WebSocket 请求和未收到响应的请求为 None
+    pub http_status: Option<u16>,
+    /// JSON-RPC `error.code`
+    pub rpc_error_code: Option<i64>,
+    /// JSON-RPC `error.data`
+    pub rpc_error_data: Option<Value>,
+    /// 节点通过 Retry-After 要求的等待时间
+    pub retry_after: Option<Duration>,
+    /// 尝试次数（包括第一次）
+    pub attempts: u32,
+    /// 第一次尝试是否成功
+    pub first_attempt_success: bool,
+    /// 包含所有重试和退避等待的总延迟（毫秒）
+    pub latency_with_retries_ms: f64,
+    /// 检查的期望值断言数，调用失败时不检查
+    pub assertions_checked: usize,
+    /// 未通过的断言及原因
+    pub assertion_failures: Vec<String>,
     /// 时间戳
+    #[allow(dead_code)]
     pub timestamp: chrono::DateTime<Utc>,
 }
 
+impl RpcResult {
+    /// 是否被限流（HTTP 429 或限流类 JSON-RPC 错误）
+    pub fn is_rate_limited(&self) -> bool {
+        self.error_kind == Some(ErrorKind::RateLimited)
+    }
+
+    /// 是否收到了没有错误但未通过校验的结果
+    pub fn is_invalid_result(&self) -> bool {
+        self.error_kind == Some(ErrorKind::InvalidResult)
+    }
+
+    /// 有效吞吐量（KiB/s）：解压后的响应体大小除以请求延迟
+    pub fn throughput_kib_s(&self) -> Option<f64> {
+        if !self.success || self.latency_ms <= 0.0 {
+            return None;
+        }
+        Some(self.transfer.decoded_bytes as f64 / 1024.0 / (self.latency_ms / 1000.0))
+    }
+}
+
+/// 一次 WebSocket 重连事件
+#[derive(Debug, Clone)]
+pub struct ReconnectEvent {
+    /// 端点URL
+    pub endpoint: String,
+    /// 重连开始时间
+    pub timestamp: chrono::DateTime<Utc>,
+    /// 尝试次数
+    pub attempts: u32,
+    /// 从开始重连到成功（或放弃）的耗时（毫秒）
+    pub duration_ms: f64,
+    /// 是否重连成功
+    pub success: bool,
+}
+
+/// WebSocket 连接流
+pub type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
 /// WebSocket连接管理器
 pub struct WebSocketManager {
-    connections: HashMap<String, WebSocketStream<MaybeTlsStream<TcpStream>>>,
+    connections: HashMap<String, WsStream>,
+    /// 曾经成功建立过连接的端点，再次连接时视为重连
+    connected_once: HashSet<String>,
+    /// 重连失败的端点及下次允许重连的时间，在此之前的请求直接失败
+    down_until: HashMap<String, Instant>,
+    reconnects: Vec<ReconnectEvent>,
+    /// 下一个请求ID，用于把响应与请求对应起来
+    next_id: u64,
     config: Config,
 }
 
@@ -76,103 +596,255 @@ impl WebSocketManager {
     pub fn new(config: Config) -> Self {
         Self {
             connections: HashMap::new(),
+            connected_once: HashSet::new(),
+            down_until: HashMap::new(),
+            reconnects: Vec::new(),
+            next_id: 1,
             config,
         }
     }
 
-    /// 获取或创建WebSocket连接
-    async fn get_connection(&mut self, url: &str) -> Result<&mut WebSocketStream<MaybeTlsStream<TcpStream>>, RpcError> {
-        if !self.connections.contains_key(url) {
-            debug!("创建新的WebSocket连接: {}", url);
-            let ws_url = Url::parse(url)
-                .map_err(|e| RpcError::ConfigError(format!("无效的WebSocket URL: {}", e)))?;
-            
-            let (ws_stream, _) = connect_async(ws_url).await
-                .map_err(|e| RpcError::WebSocketError(format!("连接失败: {}", e)))?;
+    /// 当前配置
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// 已记录的重连事件
+    pub fn reconnect_events(&self) -> &[ReconnectEvent] {
+        &self.reconnects
+    }
+
+    /// 建立一条新的WebSocket连接，配置了代理时经代理建立隧道
+    async fn connect(&self, chain: &Chain) -> Result<WsStream, RpcError> {
+        let url = chain.rpc_url.as_str();
+        debug!("创建新的WebSocket连接: {}", url);
+        let ws_url = Url::parse(url)
+            .map_err(|e| RpcError::ConfigError(format!("无效的WebSocket URL: {}", e)))?;
+        let host = ws_url
+            .host_str()
+            .ok_or_else(|| RpcError::ConfigError(format!("WebSocket URL缺少主机名: {}", url)))?;
+        let port = ws_url
+            .port_or_known_default()
+            .ok_or_else(|| RpcError::ConfigError(format!("WebSocket URL缺少端口: {}", url)))?;
+        
+        let tcp_stream = net::connect(host, port, chain.proxy.as_ref()).await.map_err(|e| match e {
+            RpcError::NetworkError(message) => RpcError::WebSocketError(format!("连接失败: {}", message)),
+            other => other,
+        })?;
+        
+        let ws_config = WebSocketConfig {
+            max_message_size: Some(self.config.ws_max_message_size),
+            max_frame_size: Some(self.config.ws_max_frame_size),
+            ..WebSocketConfig::default()
+        };
+        // 先完成 TLS 握手并校验证书指纹，再发送升级请求
+        let stream = if ws_url.scheme() == "wss" {
+            MaybeTlsStream::NativeTls(chain.tls.handshake(host, tcp_stream).await?)
+        } else {
+            MaybeTlsStream::Plain(tcp_stream)
+        };
+        
+        let (ws_stream, _) = client_async_with_config(ws_url.as_str(), stream, Some(ws_config)).await
+            .map_err(|e| RpcError::WebSocketError(format!("连接失败: {}", e)))?;
+        
+        match &chain.proxy {
+            Some(proxy) => info!("WebSocket连接已建立: {} (代理: {})", url, proxy.redacted()),
+            None => info!("WebSocket连接已建立: {}", url),
+        }
+        Ok(ws_stream)
+    }
+
+    /// 按退避策略最多尝试 `max_attempts` 次重连，并记录重连事件
+    async fn reconnect(&mut self, chain: &Chain, max_attempts: u32) -> Result<WsStream, RpcError> {
+        let url = chain.rpc_url.as_str();
+        let timestamp = Utc::now();
+        let start = Instant::now();
+        let mut attempts = 0;
+        
+        let result = loop {
+            attempts += 1;
+            tokio::time::sleep(self.config.reconnect_backoff(attempts)).await;
             
+            match self.connect(chain).await {
+                Ok(stream) => break Ok(stream),
+                Err(e) if attempts >= max_attempts => break Err(e),
+                Err(e) => warn!("WebSocket重连失败 ({}/{}): {} - {}", attempts, max_attempts, url, e),
+            }
+        };
+        
+        let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+        info!("WebSocket重连{}: {} ({} 次尝试, {:.2}ms)",
+              if result.is_ok() { "成功" } else { "失败" }, url, attempts, duration_ms);
+        
+        self.reconnects.push(ReconnectEvent {
+            endpoint: url.to_string(),
+            timestamp,
+            attempts,
+            duration_ms,
+            success: result.is_ok(),
+        });
+        
+        result
+    }
+
+    /// 获取或创建WebSocket连接，曾经断开的连接会自动重连
+    ///
+    /// 重连失败后端点被标记为不可用，在 `ws_reconnect_max_backoff_ms` 内的请求直接失败，
+    /// 之后的请求只尝试一次重连，一个失效的端点不会让每个请求都等待完整的退避重连。
+    async fn get_connection(&mut self, chain: &Chain) -> Result<&mut WsStream, RpcError> {
+        let url = chain.rpc_url.as_str();
+        if !self.connections.contains_key(url) {
+            let ws_stream = if self.connected_once.contains(url) {
+                let max_attempts = match self.down_until.get(url) {
+                    Some(until) if Instant::now() < *until => {
+                        return Err(RpcError::WebSocketError(format!("端点重连失败，暂停重连: {}", url)));
+                    }
+                    Some(_) => 1,
+                    None => self.config.ws_reconnect_attempts.max(1),
+                };
+                match self.reconnect(chain, max_attempts).await {
+                    Ok(ws_stream) => {
+                        self.down_until.remove(url);
+                        ws_stream
+                    }
+                    Err(e) => {
+                        let cooldown = Duration::from_millis(self.config.ws_reconnect_max_backoff_ms);
+                        self.down_until.insert(url.to_string(), Instant::now() + cooldown);
+                        return Err(e);
+                    }
+                }
+            } else {
+                self.connect(chain).await?
+            };
+            self.connected_once.insert(url.to_string());
             self.connections.insert(url.to_string(), ws_stream);
-            info!("WebSocket连接已建立: {}", url);
         }
         
         Ok(self.connections.get_mut(url).unwrap())
     }
 
+    /// 丢弃失效的连接，下次请求时重连
+    fn drop_connection(&mut self, url: &str) {
+        if self.connections.remove(url).is_some() {
+            warn!("WebSocket连接已失效，将在下次请求时重连: {}", url);
+        }
+    }
+
+    /// 在独立连接上发起 eth_subscribe 订阅
+    ///
+    /// 订阅通知会持续推送，因此不与普通请求共用连接。
+    pub async fn subscribe(&self, chain: &Chain, params: &[Value]) -> Result<WsSubscription, RpcError> {
+        let url = chain.rpc_url.as_str();
+        let mut stream = self.connect(chain).await?;
+        
+        let request_body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_subscribe",
+            "params": params
+        });
+        
+        debug!("发送订阅请求: {:?} 到 {}", params, url);
+        stream.send(Message::Text(request_body.to_string())).await
+            .map_err(|e| RpcError::WebSocketError(format!("发送订阅请求失败: {}", e)))?;
+        
+        let deadline = Instant::now() + Duration::from_secs(self.config.ws_timeout_secs);
+        loop {
+            let response = match read_json(&mut stream, deadline).await? {
+                Some(response) => response,
+                None => {
+                    return Err(RpcError::TimeoutError(format!(
+                        "订阅请求超时({}秒)", self.config.ws_timeout_secs
+                    )))
+                }
+            };
+            
+            if response.get("id") != Some(&json!(1)) {
+                continue;
+            }
+            
+            if let Some(error) = response.get("error") {
+                let message = error
+                    .get("message")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("未知错误");
+                return Err(RpcError::JsonRpcError(format!("订阅失败: {}", message)));
+            }
+            
+            let id = response
+                .get("result")
+                .and_then(|r| r.as_str())
+                .ok_or_else(|| RpcError::JsonRpcError("订阅响应缺少订阅ID".to_string()))?
+                .to_string();
+            
+            info!("订阅已建立: {} (ID: {})", url, id);
+            return Ok(WsSubscription { stream, id });
+        }
+    }
+
     /// 发送WebSocket RPC请求
     pub async fn send_request(
         &mut self,
-        url: &str,
+        chain: &Chain,
         method: &str,
         params: &[Value],
-    ) -> Result<(bool, f64, Option<String>, Value), RpcError> {
-        let start = Instant::now();
-        
+    ) -> Result<RpcResponse, RpcError> {
         // 先获取超时配置，避免借用冲突
-        let ws_timeout_secs = self.config.ws_timeout_secs;
-        
-        // 获取连接
-        let ws_stream = self.get_connection(url).await?;
+        let ws_timeout_secs = self.config.timeout_secs(method, &chain.connection_type);
+        let id = self.next_id;
+        self.next_id += 1;
         
-        // 创建请求
-        let request_body = json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": method,
-            "params": params
-        });
+        // 获取连接，建立连接和重连的耗时不计入请求延迟，重连耗时记录在重连事件中
+        let url = chain.rpc_url.as_str();
+        let ws_stream = self.get_connection(chain).await?;
+        let start = Instant::now();
         
-        // 发送请求
         debug!("发送WebSocket请求: {} 到 {}", method, url);
-        let request_message = Message::Text(request_body.to_string());
-        ws_stream.send(request_message).await
-            .map_err(|e| RpcError::WebSocketError(format!("发送消息失败: {}", e)))?;
-        
-        // 等待响应
-        let timeout_duration = Duration::from_secs(ws_timeout_secs);
-        let timeout = tokio::time::sleep(timeout_duration);
-        let response_future = ws_stream.next();
-        
-        pin_mut!(response_future);
-        pin_mut!(timeout);
-        
-        let response = match future::select(response_future, timeout).await {
-            Either::Left((Some(Ok(response)), _)) => {
-                debug!("收到WebSocket响应");
-                response
-            },
-            Either::Left((Some(Err(e)), _)) => {
-                return Err(RpcError::WebSocketError(format!("响应错误: {}", e)));
-            },
-            Either::Left((None, _)) => {
-                return Err(RpcError::WebSocketError("连接已关闭".to_string()));
-            },
-            Either::Right((_, _)) => {
-                return Err(RpcError::TimeoutError(format!("请求超时({}秒)", ws_timeout_secs)));
-            },
-        };
+        let result = exchange(ws_stream, id, method, params, ws_timeout_secs).await;
         
-        let latency = start.elapsed().as_secs_f64() * 1000.0;
+        // 连接层错误或超时通常意味着连接已不可用，丢弃后下次请求重连
+        if matches!(result, Err(RpcError::WebSocketError(_)) | Err(RpcError::TimeoutError(_))) {
+            self.drop_connection(url);
+        }
+        let (body, transfer) = result?;
         
-        // 处理响应
-        let response_text = match response {
-            Message::Text(text) => text,
-            _ => return Err(RpcError::WebSocketError("收到非文本响应".to_string())),
-        };
+        Ok(RpcResponse {
+            latency_ms: start.elapsed().as_secs_f64() * 1000.0,
+            body,
+            phases: PhaseTimings::default(),
+            transfer,
+            http_status: None,
+        })
+    }
+
+    /// 在全新的WebSocket连接上发送请求，延迟包含建立连接的耗时
+    pub async fn send_request_cold(
+        &mut self,
+        chain: &Chain,
+        method: &str,
+        params: &[Value],
+    ) -> Result<RpcResponse, RpcError> {
+        let start = Instant::now();
+        let id = self.next_id;
+        self.next_id += 1;
         
-        let response_body: Value = serde_json::from_str(&response_text)
-            .map_err(|e| RpcError::JsonRpcError(format!("解析响应失败: {}", e)))?;
+        let mut ws_stream = self.connect(chain).await?;
         
-        let success = response_body.get("error").is_none();
-        let error = if !success {
-            response_body
-                .get("error")
-                .and_then(|e| e.get("message"))
-                .and_then(|m| m.as_str())
-                .map(|s| s.to_string())
-        } else {
-            None
-        };
+        debug!("发送冷连接WebSocket请求: {} 到 {}", method, chain.rpc_url);
+        let timeout_secs = self.config.timeout_secs(method, &chain.connection_type);
+        let result = exchange(&mut ws_stream, id, method, params, timeout_secs).await;
+        let latency = start.elapsed().as_secs_f64() * 1000.0;
+        
+        let _ = ws_stream.send(Message::Close(None)).await;
+        let (body, transfer) = result?;
         
-        Ok((success, latency, error, response_body))
+        Ok(RpcResponse {
+            latency_ms: latency,
+            body,
+            phases: PhaseTimings::default(),
+            transfer,
+            http_status: None,
+        })
     }
 
     /// 关闭所有连接
@@ -184,111 +856,463 @@ impl WebSocketManager {
     }
 }
 
+/// 在已建立的连接上发送一个请求并等待ID匹配的响应
+///
+/// 订阅通知和此前超时请求的迟到响应会被跳过。
+async fn exchange(
+    ws_stream: &mut WsStream,
+    id: u64,
+    method: &str,
+    params: &[Value],
+    timeout_secs: u64,
+) -> Result<(Value, Transfer), RpcError> {
+    let request_body = json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": method,
+        "params": params
+    })
+    .to_string();
+    let request_bytes = request_body.len();
+    
+    ws_stream.send(Message::Text(request_body)).await
+        .map_err(|e| RpcError::WebSocketError(format!("发送消息失败: {}", e)))?;
+    
+    let deadline = Instant::now() + Duration::from_secs(timeout_secs);
+    loop {
+        let payload = match read_payload(ws_stream, deadline).await? {
+            Some(payload) => payload,
+            None => return Err(RpcError::TimeoutError(format!("请求超时({}秒)", timeout_secs))),
+        };
+        let Some(response) = parse_json(&payload) else {
+            continue;
+        };
+        
+        if response.get("id") == Some(&json!(id)) {
+            debug!("收到WebSocket响应");
+            return Ok((response, Transfer::uncompressed(request_bytes, payload.len())));
+        }
+        
+        let kind = response.get("method").and_then(|m| m.as_str()).unwrap_or("过期响应");
+        debug!("跳过与请求无关的WebSocket消息: {}", kind);
+    }
+}
+
+/// 提取 JSON-RPC 响应（或 GraphQL 响应 `errors` 中第一项）的错误，成功的响应返回 None
+///
+/// 表示限流的错误码和错误信息返回 `RpcError::RateLimited`。
+fn rpc_error(response_body: &Value) -> Option<RpcError> {
+    let error = response_body
+        .get("error")
+        .or_else(|| response_body.get("errors").and_then(|e| e.get(0)))?;
+    let message = error
+        .get("message")
+        .and_then(|m| m.as_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| error.to_string());
+    if ratelimit::is_rate_limit_error(error) {
+        return Some(RpcError::RateLimited { message, retry_after: None, http_status: None });
+    }
+    Some(RpcError::ErrorResponse {
+        code: error.get("code").and_then(|c| c.as_i64()),
+        message,
+        data: error.get("data").cloned(),
+    })
+}
+
+/// 读取下一条 JSON 消息，跳过无法解析的消息，到达截止时间时返回 `Ok(None)`
+async fn read_json(stream: &mut WsStream, deadline: Instant) -> Result<Option<Value>, RpcError> {
+    while let Some(payload) = read_payload(stream, deadline).await? {
+        if let Some(value) = parse_json(&payload) {
+            return Ok(Some(value));
+        }
+    }
+    Ok(None)
+}
+
+/// 解析 JSON 消息，节点的保活文本、二进制垃圾等非 JSON 消息记录 debug 日志后返回 None
+fn parse_json(payload: &[u8]) -> Option<Value> {
+    match serde_json::from_slice(payload) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            debug!("跳过无法解析的WebSocket消息 ({} 字节): {}", payload.len(), e);
+            None
+        }
+    }
+}
+
+/// 读取下一条数据消息的内容，到达截止时间时返回 `Ok(None)`
+///
+/// Ping/Pong 等控制帧由 tungstenite 自动应答后跳过，二进制帧按 UTF-8 JSON 处理。
+async fn read_payload(stream: &mut WsStream, deadline: Instant) -> Result<Option<Vec<u8>>, RpcError> {
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let message = match tokio::time::timeout(remaining, stream.next()).await {
+            Ok(Some(Ok(message))) => message,
+            Ok(Some(Err(tungstenite::Error::Capacity(e)))) => {
+                return Err(RpcError::WebSocketError(format!("消息超过大小限制: {}", e)));
+            },
+            Ok(Some(Err(e))) => return Err(RpcError::WebSocketError(format!("响应错误: {}", e))),
+            Ok(None) => return Err(RpcError::WebSocketError("连接已关闭".to_string())),
+            Err(_) => return Ok(None),
+        };
+        
+        let payload = match message {
+            Message::Text(text) => text.into_bytes(),
+            Message::Binary(data) => data,
+            Message::Close(frame) => {
+                return Err(RpcError::WebSocketError(format!("连接被对端关闭: {:?}", frame)));
+            },
+            Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => continue,
+        };
+        
+        return Ok(Some(payload));
+    }
+}
+
+/// 一个 eth_subscribe 订阅，独占一条WebSocket连接
+pub struct WsSubscription {
+    stream: WsStream,
+    /// 节点返回的订阅ID
+    pub id: String,
+}
+
+impl WsSubscription {
+    /// 等待下一条订阅通知，返回其 `params.result`
+    ///
+    /// 到达截止时间时返回 `Ok(None)`。
+    pub async fn next_notification(&mut self, deadline: Instant) -> Result<Option<Value>, RpcError> {
+        loop {
+            let notification = match read_json(&mut self.stream, deadline).await {
+                Ok(Some(notification)) => notification,
+                Ok(None) => return Ok(None),
+                Err(RpcError::JsonRpcError(e)) => {
+                    debug!("忽略无法解析的通知: {}", e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            
+            let params = match notification.get("params") {
+                Some(params) if params.get("subscription").and_then(|s| s.as_str()) == Some(self.id.as_str()) => params,
+                _ => continue,
+            };
+            
+            return Ok(params.get("result").cloned());
+        }
+    }
+
+    /// 取消订阅并关闭连接
+    pub async fn unsubscribe(mut self) {
+        let request_body = json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "eth_unsubscribe",
+            "params": [self.id]
+        });
+        let _ = self.stream.send(Message::Text(request_body.to_string())).await;
+        let _ = self.stream.send(Message::Close(None)).await;
+    }
+}
+
 /// RPC客户端管理器
 pub struct RpcManager {
-    http_client: Client,
+    /// 每个 HTTP 端点独立的客户端，按链名称索引
+    http_clients: HashMap<String, Client>,
+    /// 配置了限速的端点的令牌桶，按链名称索引
+    rate_limiters: HashMap<String, TokenBucket>,
+    /// 配置了证书指纹的 HTTPS 端点在发送第一个请求前的指纹校验，按链名称索引
+    pin_checks: HashMap<String, OnceCell<()>>,
     ws_manager: WebSocketManager,
     config: Config,
 }
 
 impl RpcManager {
-    pub fn new(config: Config) -> Self {
-        let http_client = Client::builder()
-            .timeout(Duration::from_secs(config.http_timeout_secs))
-            .build()
-            .expect("创建HTTP客户端失败");
+    /// 为每个 HTTP 端点创建客户端，端点配置了代理时客户端只经该代理连接
+    pub fn new(config: Config, chains: &[Chain]) -> Result<Self, RpcError> {
+        let mut http_clients = HashMap::new();
+        for chain in chains.iter().filter(|c| c.connection_type.is_http()) {
+            let mut builder = Client::builder()
+                .timeout(Duration::from_secs(config.http_timeout_secs));
+            if let Some(ref proxy) = chain.proxy {
+                builder = builder.proxy(proxy.to_reqwest()?);
+            }
+            builder = chain.tls.configure_reqwest(builder)?;
+            let client = builder
+                .build()
+                .map_err(|e| RpcError::ConfigError(format!("创建HTTP客户端失败: {}", e)))?;
+            http_clients.insert(chain.name.clone(), client);
+        }
         
-        let ws_manager = WebSocketManager::new(Config::default());
+        let rate_limiters = chains
+            .iter()
+            .filter_map(|chain| chain.rate_limit.map(|rate| (chain.name.clone(), TokenBucket::new(rate, config.rate_limit_burst))))
+            .collect();
         
-        Self {
-            http_client,
+        let pin_checks = chains
+            .iter()
+            .filter(|chain| chain.connection_type.is_http() && !chain.tls.pins.is_empty() && chain.rpc_url.starts_with("https"))
+            .map(|chain| (chain.name.clone(), OnceCell::new()))
+            .collect();
+        
+        let ws_manager = WebSocketManager::new(config.clone());
+        
+        Ok(Self {
+            http_clients,
+            rate_limiters,
+            pin_checks,
             ws_manager,
             config,
-        }
+        })
     }
 
     /// 发送HTTP RPC请求
+    ///
+    /// reqwest 未启用自动解压，Accept-Encoding 由 `compression` 决定，响应体在计时结束后自行解压，
+    /// 以便记录线路上的实际字节数。
     async fn send_http_request(
         &self,
-        rpc_url: &str,
-        method: &str,
-        params: &[Value],
-    ) -> Result<(bool, f64, Option<String>, Value), RpcError> {
+        chain: &Chain,
+        method: &RpcMethod,
+        compression: Compression,
+    ) -> Result<RpcResponse, RpcError> {
+        let rpc_url = chain.rpc_url.as_str();
+        let http_client = self.http_clients
+            .get(&chain.name)
+            .ok_or_else(|| RpcError::ConfigError(format!("没有为 {} 创建HTTP客户端", chain.name)))?;
+        let timeout_secs = self.config.timeout_secs(&method.name, &chain.connection_type);
+        
+        // 校验失败时不缓存结果，之后的请求仍在发送前重新校验
+        if let Some(pin_check) = self.pin_checks.get(&chain.name) {
+            pin_check
+                .get_or_try_init(|| probe::check_pin(chain, Duration::from_secs(timeout_secs)))
+                .await?;
+        }
+        
         let start = Instant::now();
         
-        let request_body = json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": method,
-            "params": params
-        });
+        let request_body = http_request_body(chain, method)?;
+        let request_bytes = request_body.len();
         
-        debug!("发送HTTP请求: {} 到 {}", method, rpc_url);
+        debug!("发送HTTP请求: {} 到 {}", method.name, rpc_url);
         
-        let response = self.http_client
+        let mut request = http_client
             .post(rpc_url)
-            .json(&request_body)
+            .timeout(Duration::from_secs(timeout_secs))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(request_body);
+        if let Some(encoding) = compression.accept_encoding() {
+            request = request.header(reqwest::header::ACCEPT_ENCODING, encoding);
+        }
+        if let Some(ref secret) = chain.jwt_secret {
+            request = request.header(reqwest::header::AUTHORIZATION, secret.authorization());
+        }
+        
+        let response = request
             .send()
             .await
-            .map_err(|e| RpcError::NetworkError(format!("HTTP请求失败: {}", e)))?;
+            .map_err(|e| if e.is_timeout() {
+                RpcError::TimeoutError(format!("请求超时({}秒)", timeout_secs))
+            } else if tls::is_tls_error(&e) {
+                RpcError::TlsError(format!("HTTP请求失败: {}", e))
+            } else {
+                RpcError::NetworkError(format!("HTTP请求失败: {}", e))
+            })?;
         
-        let latency = start.elapsed().as_secs_f64() * 1000.0;
+        let ttfb_ms = start.elapsed().as_secs_f64() * 1000.0;
+        
+        // 复用的连接同样携带握手时的证书信息，在解读响应状态之前校验，
+        // 指纹不匹配的对端返回的 429 等状态不会被当作限流
+        if !chain.tls.pins.is_empty() {
+            let peer_certificate = response
+                .extensions()
+                .get::<reqwest::tls::TlsInfo>()
+                .and_then(|info| info.peer_certificate());
+            chain.tls.verify_pin(peer_certificate)?;
+        }
+        
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok());
+        ratelimit::check_http_status(status, retry_after)?;
         
-        let response_body: Value = response
-            .json()
+        let content_encoding = response
+            .headers()
+            .get(reqwest::header::CONTENT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        
+        let bytes = response
+            .bytes()
             .await
-            .map_err(|e| RpcError::JsonRpcError(format!("解析HTTP响应失败: {}", e)))?;
-        
-        let success = response_body.get("error").is_none();
-        let error = if !success {
-            response_body
-                .get("error")
-                .and_then(|e| e.get("message"))
-                .and_then(|m| m.as_str())
-                .map(|s| s.to_string())
-        } else {
-            None
+            .map_err(|e| if e.is_timeout() {
+                RpcError::TimeoutError(format!("请求超时({}秒)", timeout_secs))
+            } else {
+                RpcError::NetworkError(format!("读取HTTP响应失败: {}", e))
+            })?;
+        
+        let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+        
+        let phases = PhaseTimings {
+            ttfb_ms: Some(ttfb_ms),
+            body_ms: Some(latency_ms - ttfb_ms),
+            ..PhaseTimings::default()
         };
+        decode_http_response(latency_ms, request_bytes, status, bytes.to_vec(), content_encoding, phases)
+    }
+
+    /// 在全新连接上发送HTTP RPC请求，并记录建连各阶段耗时
+    async fn send_http_request_cold(
+        &self,
+        chain: &Chain,
+        method: &RpcMethod,
+        compression: Compression,
+    ) -> Result<RpcResponse, RpcError> {
+        let rpc_url = chain.rpc_url.as_str();
+        let start = Instant::now();
+        
+        let request_body = http_request_body(chain, method)?;
+        
+        debug!("发送冷连接HTTP请求: {} 到 {}", method.name, rpc_url);
+        
+        let response = probe::post_json(
+            chain,
+            request_body.as_bytes(),
+            compression.accept_encoding(),
+            Duration::from_secs(self.config.timeout_secs(&method.name, &chain.connection_type)),
+        ).await?;
+        
+        let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
         
-        Ok((success, latency, error, response_body))
+        ratelimit::check_http_status(response.status, response.retry_after.as_deref())?;
+        
+        decode_http_response(
+            latency_ms,
+            request_body.len(),
+            response.status,
+            response.body,
+            response.content_encoding,
+            response.phases,
+        )
     }
 
-    /// 测试单个RPC方法
-    pub async fn test_method(&mut self, chain: &Chain, method: &RpcMethod) -> RpcResult {
-        let result = match chain.connection_type {
-            ConnectionType::Http => {
-                self.send_http_request(&chain.rpc_url, &method.name, &method.params).await
-            },
-            ConnectionType::WebSocket => {
-                self.ws_manager.send_request(&chain.rpc_url, &method.name, &method.params).await
+    /// 按重试策略测试单个RPC方法，返回最后一次尝试的结果
+    ///
+    /// 只有可重试的错误分类才会重试，两次尝试之间按指数退避（带随机抖动）等待；
+    /// 启用 `honor_retry_after` 时，等待时间不少于节点要求的 Retry-After。
+    pub async fn test_method_with_retry(
+        &mut self,
+        chain: &Chain,
+        method: &RpcMethod,
+        cold: bool,
+        compression: Compression,
+    ) -> RpcResult {
+        let start = Instant::now();
+        let mut attempt = 1;
+        let mut first_attempt_success = None;
+        
+        loop {
+            let mut result = self.test_method(chain, method, cold, compression).await;
+            let first_success = *first_attempt_success.get_or_insert(result.success);
+            
+            let mut wait = self.config.retry.backoff(attempt);
+            if let (true, Some(retry_after)) = (self.config.honor_retry_after, result.retry_after) {
+                wait = wait.max(retry_after.min(ratelimit::MAX_RETRY_AFTER));
             }
-        };
-
-        match result {
-            Ok((success, latency_ms, error, _)) => {
-                RpcResult {
-                    chain: chain.name.clone(),
-                    endpoint: chain.rpc_url.clone(),
-                    method: method.name.clone(),
-                    success,
-                    latency_ms,
-                    error,
-                    timestamp: Utc::now(),
+            // 等待结束前就会到达截止时间时不再重试，保留这次的结果
+            let retry = match result.error_kind {
+                Some(kind) => {
+                    self.config.retry.should_retry(attempt, kind)
+                        && self.config.remaining().is_none_or(|remaining| wait < remaining)
                 }
-            },
-            Err(e) => {
-                error!("RPC调用失败: {}", e);
-                RpcResult {
-                    chain: chain.name.clone(),
-                    endpoint: chain.rpc_url.clone(),
-                    method: method.name.clone(),
-                    success: false,
-                    latency_ms: 0.0,
-                    error: Some(e.to_string()),
-                    timestamp: Utc::now(),
+                None => false,
+            };
+            if !retry {
+                if attempt > 1 {
+                    result.latency_with_retries_ms = start.elapsed().as_secs_f64() * 1000.0;
                 }
+                result.attempts = attempt;
+                result.first_attempt_success = first_success;
+                return result;
             }
+            
+            debug!(
+                "{} {} 第{}次尝试失败，{:?} 后重试: {}",
+                chain.name, method.name, attempt, wait, result.error.unwrap_or_default()
+            );
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
+    }
+
+    /// 测试单个RPC方法，`cold` 为 true 时在全新连接上发送请求
+    ///
+    /// `compression` 只对 HTTP 端点生效。端点配置了限速时先等待令牌，等待时间不计入延迟。
+    pub async fn test_method(
+        &mut self,
+        chain: &Chain,
+        method: &RpcMethod,
+        cold: bool,
+        compression: Compression,
+    ) -> RpcResult {
+        if let Some(rate_limiter) = self.rate_limiters.get_mut(&chain.name) {
+            rate_limiter.acquire().await;
         }
+        
+        let result = match (&chain.connection_type, cold) {
+            (ConnectionType::Http | ConnectionType::GraphQl | ConnectionType::Engine, false) => {
+                self.send_http_request(chain, method, compression).await
+            },
+            (ConnectionType::Http | ConnectionType::GraphQl | ConnectionType::Engine, true) => {
+                self.send_http_request_cold(chain, method, compression).await
+            },
+            (ConnectionType::WebSocket, false) => {
+                self.ws_manager.send_request(chain, &method.name, &method.params).await
+            },
+            (ConnectionType::WebSocket, true) => {
+                self.ws_manager.send_request_cold(chain, &method.name, &method.params).await
+            },
+        };
+
+        rpc_result(chain, method, cold, compression, result, false)
+    }
+
+    /// 在连接池中的连接上发送一个请求并返回 `result`，用于需要比较结果内容的场景
+    ///
+    /// 节点返回的错误作为 Err 返回；端点配置了限速时同样先等待令牌。不支持 GraphQL 端点。
+    pub async fn call(&mut self, chain: &Chain, method: &RpcMethod) -> Result<Value, RpcError> {
+        if let Some(rate_limiter) = self.rate_limiters.get_mut(&chain.name) {
+            rate_limiter.acquire().await;
+        }
+        let response = match chain.connection_type {
+            ConnectionType::WebSocket => self.ws_manager.send_request(chain, &method.name, &method.params).await?,
+            _ => self.send_http_request(chain, method, self.config.compression).await?,
+        };
+        match response.error() {
+            Some(error) => Err(error),
+            None => Ok(response.body.get("result").cloned().unwrap_or(Value::Null)),
+        }
+    }
+
+    /// 在连接池中的连接上测试单个 HTTP 方法
+    ///
+    /// 只需要共享引用，负载测试可以在同一个端点上同时发出多个请求；不支持 WebSocket 端点。
+    /// 负载测试每秒可能发出数百个请求，失败只记录 debug 日志，由各阶段的错误计数反映。
+    pub async fn test_http_method(
+        &self,
+        chain: &Chain,
+        method: &RpcMethod,
+        compression: Compression,
+    ) -> RpcResult {
+        let result = self.send_http_request(chain, method, compression).await;
+        rpc_result(chain, method, false, compression, result, true)
+    }
+
+    /// WebSocket 重连事件
+    pub fn reconnect_events(&self) -> &[ReconnectEvent] {
+        self.ws_manager.reconnect_events()
     }
 
     /// 关闭所有连接
@@ -297,84 +1321,782 @@ impl RpcManager {
     }
 }
 
+/// 把一次请求的结果转换为 RpcResult，`quiet` 为 true 时失败只记录 debug 日志
+fn rpc_result(
+    chain: &Chain,
+    method: &RpcMethod,
+    cold: bool,
+    compression: Compression,
+    result: Result<RpcResponse, RpcError>,
+    quiet: bool,
+) -> RpcResult {
+    let level = |level: Level| if quiet { Level::Debug } else { level };
+    match result {
+        Ok(response) => {
+            let error = response
+                .error()
+                .or_else(|| validate_result(chain, method, &response.body, level(Level::Warn)).err());
+            let error_object = response.body.get("error");
+            let (assertions_checked, assertion_failures) = match error {
+                None => check_assertions(chain, method, &response.body, level(Level::Warn)),
+                Some(_) => (0, Vec::new()),
+            };
+            RpcResult {
+                chain: chain.name.clone(),
+                endpoint: chain.rpc_url.clone(),
+                connection_type: chain.connection_type.clone(),
+                method: method.name.clone(),
+                success: error.is_none(),
+                latency_ms: response.latency_ms,
+                error: error.as_ref().map(|e| e.to_string()),
+                cold,
+                phases: response.phases,
+                compression,
+                transfer: response.transfer,
+                proxy: chain.proxy_label(),
+                error_kind: error.as_ref().map(|e| e.kind()),
+                http_status: response.http_status,
+                rpc_error_code: error_object.and_then(|e| e.get("code")).and_then(|c| c.as_i64()),
+                rpc_error_data: error_object.and_then(|e| e.get("data")).cloned(),
+                retry_after: None,
+                attempts: 1,
+                first_attempt_success: error.is_none(),
+                latency_with_retries_ms: response.latency_ms,
+                assertions_checked,
+                assertion_failures,
+                timestamp: Utc::now(),
+            }
+        },
+        Err(e) => {
+            if e.is_rate_limited() {
+                log!(level(Level::Warn), "{} 请求被限流: {}", chain.name, e);
+            } else {
+                log!(level(Level::Error), "RPC调用失败: {}", e);
+            }
+            RpcResult {
+                chain: chain.name.clone(),
+                endpoint: chain.rpc_url.clone(),
+                connection_type: chain.connection_type.clone(),
+                method: method.name.clone(),
+                success: false,
+                latency_ms: 0.0,
+                error: Some(e.to_string()),
+                cold,
+                phases: PhaseTimings::default(),
+                compression,
+                transfer: Transfer::default(),
+                proxy: chain.proxy_label(),
+                error_kind: Some(e.kind()),
+                http_status: e.http_status(),
+                rpc_error_code: None,
+                rpc_error_data: None,
+                retry_after: e.retry_after(),
+                attempts: 1,
+                first_attempt_success: false,
+                latency_with_retries_ms: 0.0,
+                assertions_checked: 0,
+                assertion_failures: Vec::new(),
+                timestamp: Utc::now(),
+            }
+        }
+    }
+}
+
+/// 按方法的校验规则检查没有错误的 JSON-RPC 响应，GraphQL 响应的结构不同，不做校验
+fn validate_result(chain: &Chain, method: &RpcMethod, body: &Value, log_level: Level) -> Result<(), RpcError> {
+    match method.validator {
+        Some(validator) if chain.connection_type != ConnectionType::GraphQl => {
+            let result = body.get("result").unwrap_or(&Value::Null);
+            validator.check(result, chain.network()).map_err(|reason| {
+                log!(log_level, "{} {} 结果校验失败: {}", chain.name, method.name, reason);
+                RpcError::InvalidResult(reason)
+            })
+        },
+        _ => Ok(()),
+    }
+}
+
+/// 检查适用于端点所属网络的期望值断言，返回检查的断言数和未通过的断言，GraphQL 响应不检查
+fn check_assertions(chain: &Chain, method: &RpcMethod, body: &Value, log_level: Level) -> (usize, Vec<String>) {
+    if chain.connection_type == ConnectionType::GraphQl {
+        return (0, Vec::new());
+    }
+    let result = body.get("result").unwrap_or(&Value::Null);
+    let mut checked = 0;
+    let mut failures = Vec::new();
+    for assertion in method.assertions.iter().filter(|a| a.applies_to(chain.network())) {
+        checked += 1;
+        if let Err(reason) = assertion.check(result) {
+            log!(log_level, "{} {} 断言失败: {}", chain.name, method.name, reason);
+            failures.push(reason);
+        }
+    }
+    (checked, failures)
+}
+
+/// 构造 HTTP 请求体：GraphQL 端点发送方法对应的查询，其余端点发送 JSON-RPC 请求
+fn http_request_body(chain: &Chain, method: &RpcMethod) -> Result<String, RpcError> {
+    let body = match chain.connection_type {
+        ConnectionType::GraphQl => {
+            let query = method.graphql.as_ref().ok_or_else(|| {
+                RpcError::ConfigError(format!("方法 {} 没有对应的GraphQL查询", method.name))
+            })?;
+            json!({ "query": query })
+        },
+        _ => json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method.name,
+            "params": method.params
+        }),
+    };
+    Ok(body.to_string())
+}
+
+/// 解压并解析 HTTP 响应体，记录压缩前后的字节数
+///
+/// 非 2xx 响应的响应体包含 JSON-RPC 错误对象时按节点错误处理，否则返回 `RpcError::HttpStatusError`。
+fn decode_http_response(
+    latency_ms: f64,
+    request_bytes: usize,
+    http_status: u16,
+    raw_body: Vec<u8>,
+    content_encoding: Option<String>,
+    phases: PhaseTimings,
+) -> Result<RpcResponse, RpcError> {
+    let response_bytes = raw_body.len();
+    let decoded = compression::decode_body(content_encoding.as_deref(), raw_body)?;
+    let status_ok = (200..300).contains(&http_status);
+    let status_error = || RpcError::HttpStatusError {
+        status: http_status,
+        message: body_excerpt(&decoded),
+    };
+    
+    let body: Value = match serde_json::from_slice(&decoded) {
+        Ok(body) => body,
+        Err(_) if !status_ok => return Err(status_error()),
+        Err(_) if decoded.iter().all(|b| b.is_ascii_whitespace()) => {
+            return Err(RpcError::JsonRpcError("HTTP响应体为空".to_string()));
+        },
+        Err(e) => return Err(RpcError::JsonRpcError(format!("解析HTTP响应失败: {}", e))),
+    };
+    let has_rpc_error = match body.get("error") {
+        Some(error) => error.is_object(),
+        None => body.get("errors").is_some(),
+    };
+    if !status_ok && !has_rpc_error {
+        return Err(status_error());
+    }
+    
+    Ok(RpcResponse {
+        latency_ms,
+        body,
+        phases,
+        transfer: Transfer {
+            request_bytes,
+            response_bytes,
+            decoded_bytes: decoded.len(),
+            content_encoding,
+        },
+        http_status: Some(http_status),
+    })
+}
+
+/// 错误信息中使用的响应体开头部分，合并连续空白
+fn body_excerpt(body: &[u8]) -> String {
+    const MAX_CHARS: usize = 200;
+    let text = String::from_utf8_lossy(body);
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return "(空响应体)".to_string();
+    }
+    match text.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
+
+/// 每个端点的测试量
+#[derive(Debug, Clone, Copy)]
+pub enum RunLength {
+    /// 每个方法测试固定次数
+    Count(usize),
+    /// 在给定时长内轮流测试所有方法（耐久测试）
+    Duration(Duration),
+}
+
+/// 耐久测试中打印进度的间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 一次完整测试运行的输出
+pub struct TestRun {
+    /// 所有 RPC 调用结果的流式统计
+    pub stats: StatsCollector,
+    /// WebSocket 重连事件
+    pub reconnects: Vec<ReconnectEvent>,
+}
+
 /// 测试所有方法
+///
+/// 默认各端点并行测试，同时进行测试的端点数不超过 `max_concurrent_requests`；
+/// 同一端点的请求依次发送，每个端点同一时刻最多只有一个请求，测量结果与串行测试可比。
+/// 交错调度时所有端点在同一个任务中轮流测试，见 [`Schedule`]。
+/// 调用结果只计入流式统计而不保存，长时间的耐久测试内存占用不会增长。
 pub async fn test_all_methods(
     chains: &[Chain],
     methods: &[RpcMethod],
-    count_per_method: usize,
-) -> Result<Vec<RpcResult>> {
-    let config = Config::default();
-    let mut rpc_manager = RpcManager::new(config);
-    let mut all_results = Vec::new();
+    length: RunLength,
+    config: Config,
+) -> Result<TestRun> {
+    // 每个端点使用独立的客户端，先全部创建好，配置错误在发出任何请求前报告
+    let managers = chains
+        .iter()
+        .map(|chain| RpcManager::new(config.clone(), std::slice::from_ref(chain)))
+        .collect::<Result<Vec<_>, _>>()?;
     
-    info!("开始测试 {} 个链上的 {} 个方法", chains.len(), methods.len());
+    if config.schedule.is_interleaved() {
+        info!("开始交错测试 {} 个链上的 {} 个方法 (调度方式: {})",
+              chains.len(), methods.len(), config.schedule_label());
+        return Ok(test_interleaved(chains, managers, methods, length, &config).await);
+    }
     
-    for (chain_idx, chain) in chains.iter().enumerate() {
-        println!("测试链[{}/{}]: {} ({}) - 端点: {}", 
-                 chain_idx + 1, chains.len(), chain.name, 
-                 if chain.connection_type == ConnectionType::WebSocket { "WebSocket" } else { "HTTP" },
-                 chain.rpc_url);
-        
-        for (i, method) in methods.iter().enumerate() {
-            print!("[{}/{}] 测试方法: {} ... ", i + 1, methods.len(), method.name);
+    let semaphore = Semaphore::new(config.max_concurrent_requests.max(1));
+    
+    info!("开始测试 {} 个链上的 {} 个方法 (并发端点数: {})",
+          chains.len(), methods.len(), config.max_concurrent_requests);
+    
+    let runs = future::join_all(chains.iter().zip(managers).enumerate().map(|(chain_idx, (chain, rpc_manager))| {
+        let semaphore = &semaphore;
+        let config = &config;
+        async move {
+            let _permit = semaphore.acquire().await.expect("信号量不会被关闭");
+            test_chain(rpc_manager, chain, chain_idx, chains.len(), methods, length, config).await
+        }
+    }))
+    .await;
+    
+    // 按端点顺序合并，与并发执行的完成顺序无关
+    let mut stats = StatsCollector::new(config.compression);
+    let mut reconnects = Vec::new();
+    for run in runs {
+        stats.merge(&run.stats);
+        reconnects.extend(run.reconnects);
+    }
+    
+    Ok(TestRun {
+        stats,
+        reconnects,
+    })
+}
+
+/// 端点要测试的方法
+///
+/// Engine API 端点测试 Engine 方法组，GraphQL 端点只测试有对应查询的方法。
+pub fn methods_for_chain<'a>(
+    chain: &Chain,
+    methods: &'a [RpcMethod],
+    engine_methods: &'a [RpcMethod],
+) -> Vec<&'a RpcMethod> {
+    match chain.connection_type {
+        ConnectionType::Engine => engine_methods.iter().collect(),
+        ConnectionType::GraphQl => methods.iter().filter(|m| m.graphql.is_some()).collect(),
+        _ => methods.iter().collect(),
+    }
+}
+
+/// 一个方法在一个端点上的进度，用于控制台输出
+#[derive(Default)]
+struct MethodProgress {
+    call_count: usize,
+    success_count: usize,
+    latency_sum: f64,
+    last_error: Option<String>,
+}
+
+impl MethodProgress {
+    fn record(&mut self, result: &RpcResult) {
+        self.call_count += 1;
+        if result.success {
+            self.success_count += 1;
+            self.latency_sum += result.latency_ms;
+        } else if let Some(ref error) = result.error {
+            self.last_error = Some(error.clone());
+        }
+    }
+
+    fn avg_latency(&self) -> f64 {
+        if self.success_count > 0 {
+            self.latency_sum / self.success_count as f64
+        } else {
+            0.0
+        }
+    }
+
+    /// 多个方法的进度之和
+    fn sum<'a>(progress: impl IntoIterator<Item = &'a MethodProgress>) -> MethodProgress {
+        progress.into_iter().fold(MethodProgress::default(), |mut total, p| {
+            total.call_count += p.call_count;
+            total.success_count += p.success_count;
+            total.latency_sum += p.latency_sum;
+            total
+        })
+    }
+}
+
+/// 打印端点名称、连接类型和要测试的方法
+fn print_chain_header(chain: &Chain, chain_idx: usize, chain_count: usize, methods: &[RpcMethod], chain_methods: &[&RpcMethod]) {
+    println!("测试链[{}/{}]: {} ({}) - 端点: {}", 
+             chain_idx + 1, chain_count, chain.name, 
+             chain.connection_type.label(),
+             chain.rpc_url);
+    if chain.connection_type == ConnectionType::GraphQl && chain_methods.len() < methods.len() {
+        println!("[{}] 跳过 {} 个没有GraphQL查询的方法", chain.name, methods.len() - chain_methods.len());
+    }
+}
+
+/// 打印一个方法在一个端点上的测试结果
+///
+/// 多个端点并行测试时输出会交错，每行带上端点名称并一次性输出。
+fn print_method_progress(chain: &Chain, method_idx: usize, method_count: usize, method: &RpcMethod, progress: &MethodProgress) {
+    match progress.last_error {
+        Some(ref error) if chain.connection_type == ConnectionType::WebSocket => {
+            println!("[{}] [{}/{}] 测试方法: {} ... 完成 ({}/{}成功, 平均: {:.2}ms) - 错误: {}", 
+                     chain.name, method_idx + 1, method_count, method.name,
+                     progress.success_count, progress.call_count, progress.avg_latency(), error);
+        },
+        _ => {
+            println!("[{}] [{}/{}] 测试方法: {} ... 完成 ({}/{}成功, 平均: {:.2}ms)", 
+                     chain.name, method_idx + 1, method_count, method.name,
+                     progress.success_count, progress.call_count, progress.avg_latency());
+        },
+    }
+}
+
+/// 打印耐久测试中一个端点的进度
+fn print_soak_progress(chain: &Chain, start: Instant, rounds: u64, progress: &MethodProgress) {
+    println!("[{}] 已运行 {}秒: {} 轮, {} 次调用, 成功率 {:.2}%, 平均: {:.2}ms",
+             chain.name, start.elapsed().as_secs(), rounds, progress.call_count,
+             progress.success_count as f64 / progress.call_count.max(1) as f64 * 100.0,
+             progress.avg_latency());
+}
+
+/// 打印耐久测试结束时一个端点的汇总
+fn print_soak_done(chain: &Chain, start: Instant, rounds: u64, progress: &MethodProgress) {
+    println!("[{}] 耐久测试完成: {}秒, {} 轮, {} 次调用 ({}成功, 平均: {:.2}ms)",
+             chain.name, start.elapsed().as_secs(), rounds, progress.call_count,
+             progress.success_count, progress.avg_latency());
+}
+
+/// 依次测试一个端点上的所有方法
+///
+/// 固定次数时逐个方法测试；耐久测试时每轮把所有方法各测试一次，直到时长结束。
+async fn test_chain(
+    mut rpc_manager: RpcManager,
+    chain: &Chain,
+    chain_idx: usize,
+    chain_count: usize,
+    methods: &[RpcMethod],
+    length: RunLength,
+    config: &Config,
+) -> TestRun {
+    let mut stats = StatsCollector::new(config.compression);
+    
+    // 等待并发名额期间可能已经到达截止时间
+    if config.remaining() == Some(Duration::ZERO) {
+        println!("[{}] 已到达测试截止时间，跳过该端点", chain.name);
+        return TestRun {
+            stats,
+            reconnects: Vec::new(),
+        };
+    }
+    
+    let engine_methods = methods::get_engine_methods();
+    let chain_methods = methods_for_chain(chain, methods, &engine_methods);
+    print_chain_header(chain, chain_idx, chain_count, methods, &chain_methods);
+    
+    let deadline_reached = warm_up(&mut rpc_manager, chain, &chain_methods, config, &mut stats).await || match length {
+        RunLength::Count(count_per_method) => {
+            test_chain_by_count(&mut rpc_manager, chain, &chain_methods, count_per_method, config, &mut stats).await
+        },
+        RunLength::Duration(duration) => {
+            test_chain_by_duration(&mut rpc_manager, chain, &chain_methods, duration, config, &mut stats).await
+        },
+    };
+    if deadline_reached {
+        warn!("{} 已到达测试截止时间，停止发出新的请求", chain.name);
+        println!("[{}] 已到达测试截止时间，剩余的方法未测试", chain.name);
+    }
+    
+    let reconnects = rpc_manager.reconnect_events().to_vec();
+    
+    // 关闭所有连接
+    rpc_manager.close().await;
+    
+    TestRun {
+        stats,
+        reconnects,
+    }
+}
+
+/// 正式测试前对每个方法发送预热请求，返回是否到达了截止时间
+///
+/// 第一个请求需要解析 DNS、建立连接和完成 TLS 握手，预热使这些开销不计入延迟统计。
+/// 预热请求总是复用连接并使用主压缩方式，结果只作为首次调用延迟和预热失败次数报告。
+async fn warm_up(
+    rpc_manager: &mut RpcManager,
+    chain: &Chain,
+    chain_methods: &[&RpcMethod],
+    config: &Config,
+    stats: &mut StatsCollector,
+) -> bool {
+    for method in chain_methods {
+        for _ in 0..config.warmup {
+            let request = rpc_manager.test_method_with_retry(chain, method, false, config.compression);
+            let result = match config.remaining() {
+                Some(remaining) => match tokio::time::timeout(remaining, request).await {
+                    Ok(result) => result,
+                    Err(_) => return true,
+                },
+                None => request.await,
+            };
             
-            let mut method_results = Vec::with_capacity(count_per_method);
-            let mut error_occurred = false;
-            let mut last_error = String::new();
+            if let Some(ref error) = result.error {
+                warn!("{} 预热 {} 失败: {}", chain.name, method.name, error);
+                println!("[{}] 预热方法: {} 失败 - 错误: {}", chain.name, method.name, error);
+            }
+            stats.record_warmup(&result);
             
-            for attempt in 0..count_per_method {
-                let result = rpc_manager.test_method(chain, method).await;
-                
-                if !result.success {
-                    error_occurred = true;
-                    if let Some(ref error) = result.error {
-                        last_error = error.clone();
-                    }
-                    
-                    // 对于WebSocket连接，如果第一次请求失败，输出详细错误并终止后续尝试
-                    if attempt == 0 && chain.connection_type == ConnectionType::WebSocket {
-                        debug!("WebSocket请求失败: {:?}", result.error);
-                        method_results.push(result);
-                        break;
+            tokio::time::sleep(config.request_pause()).await;
+        }
+    }
+    false
+}
+
+/// 每个方法测试固定次数，返回是否到达了截止时间
+async fn test_chain_by_count(
+    rpc_manager: &mut RpcManager,
+    chain: &Chain,
+    chain_methods: &[&RpcMethod],
+    count_per_method: usize,
+    config: &Config,
+    stats: &mut StatsCollector,
+) -> bool {
+    for (i, method) in chain_methods.iter().enumerate() {
+        debug!("{}: {}", method.name, method.description);
+        
+        let mut progress = MethodProgress::default();
+        let mut deadline_reached = false;
+        for _ in 0..count_per_method {
+            if !test_round(rpc_manager, chain, method, config, stats, &mut progress).await {
+                deadline_reached = true;
+                break;
+            }
+            
+            // 添加短暂延迟，避免过度请求
+            tokio::time::sleep(config.request_pause()).await;
+        }
+        
+        print_method_progress(chain, i, chain_methods.len(), method, &progress);
+        
+        if deadline_reached {
+            return true;
+        }
+    }
+    false
+}
+
+/// 在给定时长内轮流测试所有方法，定期打印进度，返回是否到达了截止时间
+async fn test_chain_by_duration(
+    rpc_manager: &mut RpcManager,
+    chain: &Chain,
+    chain_methods: &[&RpcMethod],
+    duration: Duration,
+    config: &Config,
+    stats: &mut StatsCollector,
+) -> bool {
+    let start = Instant::now();
+    let end = start + duration;
+    let mut next_progress = start + PROGRESS_INTERVAL;
+    let mut progress = MethodProgress::default();
+    let mut rounds = 0u64;
+    
+    let deadline_reached = 'rounds: loop {
+        for method in chain_methods {
+            if Instant::now() >= end {
+                break 'rounds false;
+            }
+            if !test_round(rpc_manager, chain, method, config, stats, &mut progress).await {
+                break 'rounds true;
+            }
+        }
+        rounds += 1;
+        
+        if Instant::now() >= next_progress {
+            print_soak_progress(chain, start, rounds, &progress);
+            next_progress += PROGRESS_INTERVAL;
+        }
+        
+        // 添加短暂延迟，避免过度请求
+        tokio::time::sleep(config.request_pause()).await;
+    };
+    
+    print_soak_done(chain, start, rounds, &progress);
+    deadline_reached
+}
+
+/// 交错测试的一个请求位置：端点下标和该端点方法列表中的下标
+type Slot = (usize, usize);
+
+/// 在同一个任务中交错测试所有端点的状态
+struct Interleaving<'a> {
+    chains: &'a [Chain],
+    managers: Vec<RpcManager>,
+    chain_methods: Vec<Vec<&'a RpcMethod>>,
+    /// 按方法名称分组的请求位置，同一方法在各端点上相邻
+    groups: Vec<Vec<Slot>>,
+    progress: Vec<Vec<MethodProgress>>,
+    rng: StdRng,
+}
+
+impl Interleaving<'_> {
+    /// 一轮的请求顺序：每个端点的每个方法各一次，随机调度时打乱顺序
+    fn round_order(&mut self, schedule: Schedule) -> Vec<Slot> {
+        let mut order: Vec<Slot> = self.groups.iter().flatten().copied().collect();
+        if schedule == Schedule::Random {
+            order.shuffle(&mut self.rng);
+        }
+        order
+    }
+
+    /// 对一个位置执行一轮请求，到达截止时间时返回 false
+    async fn run(&mut self, (chain_idx, method_idx): Slot, config: &Config, stats: &mut StatsCollector) -> bool {
+        test_round(
+            &mut self.managers[chain_idx],
+            &self.chains[chain_idx],
+            self.chain_methods[chain_idx][method_idx],
+            config,
+            stats,
+            &mut self.progress[chain_idx][method_idx],
+        ).await
+    }
+
+    fn print_progress(&self, (chain_idx, method_idx): Slot) {
+        print_method_progress(
+            &self.chains[chain_idx],
+            method_idx,
+            self.chain_methods[chain_idx].len(),
+            self.chain_methods[chain_idx][method_idx],
+            &self.progress[chain_idx][method_idx],
+        );
+    }
+
+    /// 每个方法测试固定次数，返回是否到达了截止时间
+    ///
+    /// 按方法调度时一个方法的所有轮次完成后再测试下一个方法，否则每轮测试所有方法。
+    async fn run_by_count(&mut self, count_per_method: usize, config: &Config, stats: &mut StatsCollector) -> bool {
+        if config.schedule == Schedule::Method {
+            for group in self.groups.clone() {
+                let mut deadline_reached = false;
+                'rounds: for _ in 0..count_per_method {
+                    for &slot in &group {
+                        if !self.run(slot, config, stats).await {
+                            deadline_reached = true;
+                            break 'rounds;
+                        }
                     }
-                } else if attempt == 0 && chain.connection_type == ConnectionType::WebSocket {
-                    debug!("WebSocket请求成功");
+                    tokio::time::sleep(config.request_pause()).await;
+                }
+                for &slot in &group {
+                    self.print_progress(slot);
+                }
+                if deadline_reached {
+                    return true;
                 }
-                
-                method_results.push(result);
-                
-                // 添加短暂延迟，避免过度请求
-                tokio::time::sleep(Duration::from_millis(100)).await;
             }
-            
-            let success_count = method_results.iter().filter(|r| r.success).count();
-            let avg_latency = if success_count > 0 {
-                method_results.iter().filter(|r| r.success).map(|r| r.latency_ms).sum::<f64>() / success_count as f64
-            } else {
-                0.0
+            return false;
+        }
+
+        let mut deadline_reached = false;
+        'rounds: for _ in 0..count_per_method {
+            for slot in self.round_order(config.schedule) {
+                if !self.run(slot, config, stats).await {
+                    deadline_reached = true;
+                    break 'rounds;
+                }
+            }
+            tokio::time::sleep(config.request_pause()).await;
+        }
+        for chain_idx in 0..self.chains.len() {
+            for method_idx in 0..self.chain_methods[chain_idx].len() {
+                if self.progress[chain_idx][method_idx].call_count > 0 {
+                    self.print_progress((chain_idx, method_idx));
+                }
+            }
+        }
+        deadline_reached
+    }
+
+    /// 在给定时长内一轮一轮地测试所有端点和方法，返回是否到达了截止时间
+    async fn run_by_duration(&mut self, duration: Duration, config: &Config, stats: &mut StatsCollector) -> bool {
+        let start = Instant::now();
+        let end = start + duration;
+        let mut next_progress = start + PROGRESS_INTERVAL;
+        let mut rounds = 0u64;
+
+        let deadline_reached = 'rounds: loop {
+            for slot in self.round_order(config.schedule) {
+                if Instant::now() >= end {
+                    break 'rounds false;
+                }
+                if !self.run(slot, config, stats).await {
+                    break 'rounds true;
+                }
+            }
+            rounds += 1;
+
+            if Instant::now() >= next_progress {
+                for (chain, progress) in self.chains.iter().zip(&self.progress) {
+                    print_soak_progress(chain, start, rounds, &MethodProgress::sum(progress));
+                }
+                next_progress += PROGRESS_INTERVAL;
+            }
+
+            // 添加短暂延迟，避免过度请求
+            tokio::time::sleep(config.request_pause()).await;
+        };
+
+        for (chain, progress) in self.chains.iter().zip(&self.progress) {
+            print_soak_done(chain, start, rounds, &MethodProgress::sum(progress));
+        }
+        deadline_reached
+    }
+}
+
+/// 在同一个任务中交错测试所有端点，使各端点在相同的网络条件下取样
+///
+/// 同一时刻只有一个请求在进行。每轮的请求顺序由调度方式决定，随机调度使用 `schedule_seed`，
+/// 相同的种子产生相同的顺序。预热请求在交错测试开始前依次对各端点发送。
+async fn test_interleaved(
+    chains: &[Chain],
+    managers: Vec<RpcManager>,
+    methods: &[RpcMethod],
+    length: RunLength,
+    config: &Config,
+) -> TestRun {
+    let mut stats = StatsCollector::new(config.compression);
+    let engine_methods = methods::get_engine_methods();
+    let chain_methods: Vec<_> = chains
+        .iter()
+        .map(|chain| methods_for_chain(chain, methods, &engine_methods))
+        .collect();
+    for (chain_idx, chain) in chains.iter().enumerate() {
+        print_chain_header(chain, chain_idx, chains.len(), methods, &chain_methods[chain_idx]);
+    }
+
+    // 同名方法放在同一组，不同端点的方法列表可能不同（GraphQL、Engine API）
+    let mut group_names: Vec<&str> = Vec::new();
+    let mut groups: Vec<Vec<Slot>> = Vec::new();
+    for (chain_idx, list) in chain_methods.iter().enumerate() {
+        for (method_idx, method) in list.iter().enumerate() {
+            match group_names.iter().position(|&name| name == method.name) {
+                Some(group) => groups[group].push((chain_idx, method_idx)),
+                None => {
+                    group_names.push(&method.name);
+                    groups.push(vec![(chain_idx, method_idx)]);
+                },
+            }
+        }
+    }
+
+    let mut interleaving = Interleaving {
+        chains,
+        managers,
+        progress: chain_methods.iter().map(|list| list.iter().map(|_| MethodProgress::default()).collect()).collect(),
+        chain_methods,
+        groups,
+        rng: StdRng::seed_from_u64(config.schedule_seed),
+    };
+
+    let mut deadline_reached = false;
+    for (chain, (rpc_manager, list)) in chains.iter().zip(interleaving.managers.iter_mut().zip(&interleaving.chain_methods)) {
+        if warm_up(rpc_manager, chain, list, config, &mut stats).await {
+            deadline_reached = true;
+            break;
+        }
+    }
+    if !deadline_reached {
+        deadline_reached = match length {
+            RunLength::Count(count_per_method) => interleaving.run_by_count(count_per_method, config, &mut stats).await,
+            RunLength::Duration(duration) => interleaving.run_by_duration(duration, config, &mut stats).await,
+        };
+    }
+    if deadline_reached {
+        warn!("已到达测试截止时间，停止发出新的请求");
+        println!("已到达测试截止时间，剩余的请求未执行");
+    }
+
+    let mut reconnects = Vec::new();
+    for mut rpc_manager in interleaving.managers {
+        reconnects.extend(rpc_manager.reconnect_events().iter().cloned());
+        rpc_manager.close().await;
+    }
+
+    TestRun {
+        stats,
+        reconnects,
+    }
+}
+
+/// 对一个方法执行一轮请求：每种连接方式和压缩方式各一次
+///
+/// 到达截止时间后不再发出新的请求，进行中的请求也会被取消，此时返回 false。
+async fn test_round(
+    rpc_manager: &mut RpcManager,
+    chain: &Chain,
+    method: &RpcMethod,
+    config: &Config,
+    stats: &mut StatsCollector,
+    progress: &mut MethodProgress,
+) -> bool {
+    // 每轮要执行的请求：false 为热连接，true 为冷连接
+    let passes = [false, true].into_iter().filter(|&cold| if cold {
+        config.connection_mode.includes_cold()
+    } else {
+        config.connection_mode.includes_warm()
+    });
+    // HTTP 端点在压缩对比模式下依次使用各种编码，主编码总是包含在内
+    let mut compressions = vec![config.compression];
+    if config.compare_compression && chain.connection_type.is_http() {
+        compressions.extend(Compression::COMPARED.into_iter().filter(|&c| c != config.compression));
+    }
+    
+    for cold in passes {
+        for &compression in &compressions {
+            let request = rpc_manager.test_method_with_retry(chain, method, cold, compression);
+            let result = match config.remaining() {
+                Some(remaining) => match tokio::time::timeout(remaining, request).await {
+                    Ok(result) => result,
+                    Err(_) => return false,
+                },
+                None => request.await,
             };
             
-            if error_occurred && chain.connection_type == ConnectionType::WebSocket {
-                println!("完成 ({}/{}成功, 平均: {:.2}ms) - 错误: {}", 
-                         success_count, count_per_method, avg_latency, last_error);
-            } else {
-                println!("完成 ({}/{}成功, 平均: {:.2}ms)", 
-                         success_count, count_per_method, avg_latency);
+            // 单次失败（包括WebSocket断线）不会中断后续测试，断开的连接会在下次请求时重连
+            if compression == config.compression {
+                progress.record(&result);
             }
+            stats.record(&result);
             
-            all_results.extend(method_results);
-            
-            // 如果这是WebSocket链且第一个方法就失败，那么跳过该链的其他测试
-            if i == 0 && success_count == 0 && chain.connection_type == ConnectionType::WebSocket {
-                debug!("WebSocket链连接无法建立，跳过其他测试");
-                break;
+            // 先记录结果再等待，等待时间不超过距截止时间的剩余时间
+            if config.honor_retry_after {
+                if let Some(retry_after) = result.retry_after {
+                    let wait = retry_after.min(ratelimit::MAX_RETRY_AFTER);
+                    let wait = config.remaining().map_or(wait, |remaining| wait.min(remaining));
+                    info!("{} 被限流，按 Retry-After 等待 {:?}", chain.name, wait);
+                    tokio::time::sleep(wait).await;
+                }
             }
         }
     }
-    
-    // 关闭所有连接
-    rpc_manager.close().await;
-    
-    Ok(all_results)
+    true
 }
-
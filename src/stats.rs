@@ -1,11 +1,13 @@
-use crate::rpc::RpcResult;
+use crate::chains::{Chain, ConnectionType};
+use crate::compression::Compression;
+use crate::rpc::{ErrorKind, PhaseTimings, ReconnectEvent, RpcResult};
 use anyhow::Result;
+use hdrhistogram::Histogram;
 use itertools::Itertools;
 use prettytable::{format, Cell, Row, Table};
-use statrs::statistics::Statistics;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// 一个方法调用的统计指标
 #[derive(Debug, Clone)]
@@ -32,111 +34,702 @@ pub struct MethodStats {
     pub p95_latency: f64,
     /// 成功率
     pub success_rate: f64,
+    /// 冷连接调用次数
+    pub cold_call_count: usize,
+    /// 冷连接成功调用次数
+    pub cold_success_count: usize,
+    /// 冷连接成功率
+    pub cold_success_rate: f64,
+    /// 冷连接平均延迟（毫秒）
+    pub cold_avg_latency: f64,
+    /// 冷连接中位数延迟（毫秒）
+    pub cold_median_latency: f64,
+    /// 冷连接 95 百分位延迟（毫秒）
+    pub cold_p95_latency: f64,
+    /// 冷、热连接中位数延迟之差（毫秒）
+    ///
+    /// HTTP 冷连接请求使用 [`crate::probe`] 的最小化客户端，热连接请求使用 reqwest，
+    /// 该差值还包含两个 HTTP 实现的差异；建连本身的耗时见 `phases` 中的 DNS、TCP、TLS 阶段。
+    pub connection_setup_ms: f64,
+    /// 各请求阶段的平均耗时
+    pub phases: PhaseTimings,
+    /// 平均请求体字节数
+    pub avg_request_bytes: f64,
+    /// 平均响应体字节数（线路上，压缩后）
+    pub avg_response_bytes: f64,
+    /// 平均解压后响应体字节数
+    pub avg_decoded_bytes: f64,
+    /// 平均有效吞吐量（KiB/s）
+    pub avg_throughput_kib_s: f64,
+    /// 节点返回的 Content-Encoding，多种时以 / 分隔，未压缩为 identity
+    pub content_encoding: String,
+    /// 使用的代理（隐去密码），直连时为空
+    pub proxy: String,
+    /// 被限流的调用次数
+    pub rate_limited_count: usize,
+    /// 节点没有返回错误但结果未通过校验的调用次数
+    pub invalid_result_count: usize,
+    /// 各 HTTP 状态码出现的次数，例如 `200:9 503:1`
+    pub http_status_counts: String,
+    /// 失败调用按错误分类的次数，例如 `method_not_found:10`
+    pub error_kind_counts: String,
+    /// 各 JSON-RPC 错误码出现的次数，例如 `-32601:10`
+    pub rpc_error_code_counts: String,
+    /// 第一次尝试即成功的比例
+    pub first_attempt_success_rate: f64,
+    /// 重试的总次数（不包括第一次尝试）
+    pub retry_count: usize,
+    /// 包含重试的平均延迟（毫秒）
+    pub avg_latency_with_retries: f64,
+    /// 包含重试的 95 百分位延迟（毫秒）
+    pub p95_latency_with_retries: f64,
+    /// 预热请求次数，不计入以上指标
+    pub warmup_count: usize,
+    /// 失败的预热请求次数
+    pub warmup_error_count: usize,
+    /// 第一个预热请求的延迟（毫秒），包含建立连接的开销；没有预热或第一个预热请求失败时为 None
+    pub first_call_latency: Option<f64>,
+    /// 检查了期望值断言且全部通过的调用次数
+    pub assertion_pass_count: usize,
+    /// 有断言未通过的调用次数
+    pub assertion_fail_count: usize,
+    /// 第一次未通过的断言及原因
+    pub assertion_failure: String,
 }
 
-/// 根据 RPC 调用结果计算统计指标
-pub fn calculate_stats(results: &[RpcResult]) -> Vec<MethodStats> {
-    let mut stats_map: HashMap<(String, String, String), Vec<&RpcResult>> = HashMap::new();
-    
-    // 按链、端点和方法分组结果
-    for result in results {
-        let key = (result.chain.clone(), result.endpoint.clone(), result.method.clone());
-        stats_map.entry(key).or_default().push(result);
+impl MethodStats {
+    /// 除限流和结果校验失败以外的失败次数
+    pub fn error_count(&self) -> usize {
+        self.call_count - self.success_count - self.rate_limited_count - self.invalid_result_count
     }
-    
-    // 计算每个组的统计指标
-    stats_map
-        .into_iter()
-        .map(|((chain, endpoint, method), group_results)| {
-            let call_count = group_results.len();
-            let success_results: Vec<_> = group_results.iter().filter(|r| r.success).cloned().collect();
-            let success_count = success_results.len();
-            let success_rate = success_count as f64 / call_count as f64;
-            
-            // 如果没有成功的结果，返回全零的统计数据
-            if success_count == 0 {
-                return MethodStats {
-                    chain,
-                    endpoint,
-                    method,
-                    call_count,
-                    success_count,
-                    min_latency: 0.0,
-                    max_latency: 0.0,
-                    avg_latency: 0.0,
-                    median_latency: 0.0,
-                    p95_latency: 0.0,
-                    success_rate,
-                };
+}
+
+/// 一组延迟样本的汇总
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LatencySummary {
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub median: f64,
+    pub p95: f64,
+}
+
+/// 延迟分布，以微秒记录到 HDR 直方图（3 位有效数字）中，内存占用与样本数无关
+///
+/// 最小、最大和平均延迟精确计算，中位数和百分位数来自直方图。直方图在第一个样本到来时才分配。
+#[derive(Debug, Clone, Default)]
+struct LatencyHistogram {
+    histogram: Option<Histogram<u64>>,
+    sum_ms: f64,
+    min_ms: f64,
+    max_ms: f64,
+}
+
+impl LatencyHistogram {
+    fn record(&mut self, latency_ms: f64) {
+        let histogram = self
+            .histogram
+            .get_or_insert_with(|| Histogram::new(3).expect("直方图参数有效"));
+        histogram
+            .record((latency_ms * 1000.0).round().max(1.0) as u64)
+            .expect("直方图可自动扩展");
+        if histogram.len() == 1 {
+            self.min_ms = latency_ms;
+            self.max_ms = latency_ms;
+        } else {
+            self.min_ms = self.min_ms.min(latency_ms);
+            self.max_ms = self.max_ms.max(latency_ms);
+        }
+        self.sum_ms += latency_ms;
+    }
+
+    fn merge(&mut self, other: &LatencyHistogram) {
+        let Some(ref other_histogram) = other.histogram else {
+            return;
+        };
+        match self.histogram {
+            Some(ref mut histogram) => {
+                histogram.add(other_histogram).expect("直方图可自动扩展");
+                self.min_ms = self.min_ms.min(other.min_ms);
+                self.max_ms = self.max_ms.max(other.max_ms);
+                self.sum_ms += other.sum_ms;
             }
-            
-            // 收集延迟数据
-            let latencies: Vec<f64> = success_results
-                .iter()
-                .map(|r| r.latency_ms)
-                .collect();
-            
-            // 计算统计指标
-            let min_latency = latencies.iter().copied().min_by(|a, b| a.partial_cmp(b).unwrap()).unwrap_or(0.0);
-            let max_latency = latencies.iter().copied().max_by(|a, b| a.partial_cmp(b).unwrap()).unwrap_or(0.0);
-            let avg_latency = latencies.iter().sum::<f64>() / latencies.len() as f64;
-            
-            // 排序计算中位数和百分位数
-            let mut sorted_latencies = latencies.clone();
-            sorted_latencies.sort_by(|a, b| a.partial_cmp(b).unwrap());
-            
-            let median_latency = if sorted_latencies.is_empty() {
+            None => *self = other.clone(),
+        }
+    }
+
+    fn count(&self) -> u64 {
+        self.histogram.as_ref().map_or(0, |h| h.len())
+    }
+
+    /// 汇总指标，没有样本时返回全零
+    fn summary(&self) -> LatencySummary {
+        let Some(ref histogram) = self.histogram else {
+            return LatencySummary::default();
+        };
+        let quantile = |q: f64| histogram.value_at_quantile(q) as f64 / 1000.0;
+        LatencySummary {
+            min: self.min_ms,
+            max: self.max_ms,
+            avg: self.sum_ms / histogram.len() as f64,
+            // 直方图的值是桶的上界，不超过实际的最大值
+            median: quantile(0.5).min(self.max_ms),
+            p95: quantile(0.95).min(self.max_ms),
+        }
+    }
+}
+
+/// 各阶段耗时的累计，每个阶段只统计记录了该阶段的成功调用
+#[derive(Debug, Clone, Default)]
+struct PhaseAccumulator {
+    /// 依次为 DNS、连接、TLS、写请求、首字节和读响应体的 (总耗时, 次数)
+    totals: [(f64, usize); 6],
+}
+
+impl PhaseAccumulator {
+    fn values(phases: &PhaseTimings) -> [Option<f64>; 6] {
+        [
+            phases.dns_ms,
+            phases.connect_ms,
+            phases.tls_ms,
+            phases.request_write_ms,
+            phases.ttfb_ms,
+            phases.body_ms,
+        ]
+    }
+
+    fn record(&mut self, phases: &PhaseTimings) {
+        for (total, value) in self.totals.iter_mut().zip(Self::values(phases)) {
+            if let Some(value) = value {
+                total.0 += value;
+                total.1 += 1;
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &PhaseAccumulator) {
+        for (total, other) in self.totals.iter_mut().zip(&other.totals) {
+            total.0 += other.0;
+            total.1 += other.1;
+        }
+    }
+
+    fn average(&self) -> PhaseTimings {
+        let average = |i: usize| {
+            let (total, count) = self.totals[i];
+            (count > 0).then(|| total / count as f64)
+        };
+        PhaseTimings {
+            dns_ms: average(0),
+            connect_ms: average(1),
+            tls_ms: average(2),
+            request_write_ms: average(3),
+            ttfb_ms: average(4),
+            body_ms: average(5),
+        }
+    }
+}
+
+/// 成功调用的传输数据量汇总
+#[derive(Debug, Clone, Default)]
+struct TransferSummary {
+    avg_request_bytes: f64,
+    avg_response_bytes: f64,
+    avg_decoded_bytes: f64,
+    avg_throughput_kib_s: f64,
+    content_encoding: String,
+}
+
+/// 成功调用的传输数据量累计
+#[derive(Debug, Clone, Default)]
+struct TransferAccumulator {
+    count: usize,
+    request_bytes: f64,
+    response_bytes: f64,
+    decoded_bytes: f64,
+    throughput_kib_s: f64,
+    throughput_count: usize,
+    content_encodings: BTreeSet<String>,
+}
+
+impl TransferAccumulator {
+    fn record(&mut self, result: &RpcResult) {
+        self.count += 1;
+        self.request_bytes += result.transfer.request_bytes as f64;
+        self.response_bytes += result.transfer.response_bytes as f64;
+        self.decoded_bytes += result.transfer.decoded_bytes as f64;
+        if let Some(throughput) = result.throughput_kib_s() {
+            self.throughput_kib_s += throughput;
+            self.throughput_count += 1;
+        }
+        let encoding = result.transfer.content_encoding.as_deref().unwrap_or("identity");
+        if !self.content_encodings.contains(encoding) {
+            self.content_encodings.insert(encoding.to_string());
+        }
+    }
+
+    fn merge(&mut self, other: &TransferAccumulator) {
+        self.count += other.count;
+        self.request_bytes += other.request_bytes;
+        self.response_bytes += other.response_bytes;
+        self.decoded_bytes += other.decoded_bytes;
+        self.throughput_kib_s += other.throughput_kib_s;
+        self.throughput_count += other.throughput_count;
+        self.content_encodings.extend(other.content_encodings.iter().cloned());
+    }
+
+    fn summary(&self) -> TransferSummary {
+        if self.count == 0 {
+            return TransferSummary::default();
+        }
+        let count = self.count as f64;
+        TransferSummary {
+            avg_request_bytes: self.request_bytes / count,
+            avg_response_bytes: self.response_bytes / count,
+            avg_decoded_bytes: self.decoded_bytes / count,
+            avg_throughput_kib_s: if self.throughput_count == 0 {
                 0.0
-            } else if sorted_latencies.len() % 2 == 0 {
-                let mid = sorted_latencies.len() / 2;
-                (sorted_latencies[mid - 1] + sorted_latencies[mid]) / 2.0
             } else {
-                sorted_latencies[sorted_latencies.len() / 2]
-            };
-            
-            let p95_index = (sorted_latencies.len() as f64 * 0.95) as usize;
-            let p95_latency = if sorted_latencies.is_empty() || p95_index >= sorted_latencies.len() {
-                if let Some(last) = sorted_latencies.last() {
-                    *last
-                } else {
-                    0.0
-                }
+                self.throughput_kib_s / self.throughput_count as f64
+            },
+            content_encoding: self.content_encodings.iter().join("/"),
+        }
+    }
+}
+
+/// 一种连接状态（冷或热）下的调用计数
+#[derive(Debug, Clone, Copy, Default)]
+struct CallCounts {
+    calls: usize,
+    successes: usize,
+    first_attempt_successes: usize,
+    retries: usize,
+    rate_limited: usize,
+    invalid_results: usize,
+}
+
+impl CallCounts {
+    fn record(&mut self, result: &RpcResult) {
+        self.calls += 1;
+        if result.success {
+            self.successes += 1;
+        }
+        if result.first_attempt_success {
+            self.first_attempt_successes += 1;
+        }
+        self.retries += (result.attempts - 1) as usize;
+        if result.is_rate_limited() {
+            self.rate_limited += 1;
+        }
+        if result.is_invalid_result() {
+            self.invalid_results += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &CallCounts) {
+        self.calls += other.calls;
+        self.successes += other.successes;
+        self.first_attempt_successes += other.first_attempt_successes;
+        self.retries += other.retries;
+        self.rate_limited += other.rate_limited;
+        self.invalid_results += other.invalid_results;
+    }
+
+    fn success_rate(&self) -> f64 {
+        match self.calls {
+            0 => 0.0,
+            calls => self.successes as f64 / calls as f64,
+        }
+    }
+}
+
+/// 一个端点上一个方法的累计统计
+#[derive(Debug, Clone, Default)]
+struct MethodAccumulator {
+    warm: CallCounts,
+    cold: CallCounts,
+    warm_latency: LatencyHistogram,
+    cold_latency: LatencyHistogram,
+    warm_latency_with_retries: LatencyHistogram,
+    cold_latency_with_retries: LatencyHistogram,
+    phases: PhaseAccumulator,
+    transfer: TransferAccumulator,
+    proxy: String,
+    http_status_counts: BTreeMap<u16, usize>,
+    error_kind_counts: BTreeMap<&'static str, usize>,
+    rpc_error_code_counts: BTreeMap<i64, usize>,
+    warmup_count: usize,
+    warmup_success_count: usize,
+    first_call_latency: Option<f64>,
+    assertion_pass_count: usize,
+    assertion_fail_count: usize,
+    assertion_failure: String,
+}
+
+impl MethodAccumulator {
+    fn call_count(&self) -> usize {
+        self.warm.calls + self.cold.calls
+    }
+
+    fn record(&mut self, result: &RpcResult) {
+        if self.call_count() == 0 {
+            self.proxy = result.proxy.clone();
+        }
+        if result.cold {
+            self.cold.record(result);
+        } else {
+            self.warm.record(result);
+        }
+        if result.success {
+            let (latency, with_retries) = if result.cold {
+                (&mut self.cold_latency, &mut self.cold_latency_with_retries)
             } else {
-                sorted_latencies[p95_index]
+                (&mut self.warm_latency, &mut self.warm_latency_with_retries)
             };
-            
-            MethodStats {
-                chain,
-                endpoint,
-                method,
-                call_count,
-                success_count,
-                min_latency,
-                max_latency,
-                avg_latency,
-                median_latency,
-                p95_latency,
-                success_rate,
+            latency.record(result.latency_ms);
+            with_retries.record(result.latency_with_retries_ms);
+            self.phases.record(&result.phases);
+            self.transfer.record(result);
+        }
+        if let Some(status) = result.http_status {
+            *self.http_status_counts.entry(status).or_default() += 1;
+        }
+        if let Some(kind) = result.error_kind {
+            *self.error_kind_counts.entry(kind.name()).or_default() += 1;
+        }
+        if let Some(code) = result.rpc_error_code {
+            *self.rpc_error_code_counts.entry(code).or_default() += 1;
+        }
+        if !result.assertion_failures.is_empty() {
+            if self.assertion_fail_count == 0 {
+                self.assertion_failure = result.assertion_failures.join("; ");
             }
-        })
-        .sorted_by(|a, b| {
-            a.chain.cmp(&b.chain).then_with(|| {
-                // 先按成功率排序（降序）
-                b.success_rate.partial_cmp(&a.success_rate).unwrap_or(std::cmp::Ordering::Equal)
-                // 如果成功率相同，按平均延迟排序（升序）
-                .then_with(|| a.avg_latency.partial_cmp(&b.avg_latency).unwrap_or(std::cmp::Ordering::Equal))
+            self.assertion_fail_count += 1;
+        } else if result.assertions_checked > 0 {
+            self.assertion_pass_count += 1;
+        }
+    }
+
+    fn record_warmup(&mut self, result: &RpcResult) {
+        if self.warmup_count == 0 && result.success {
+            self.first_call_latency = Some(result.latency_ms);
+        }
+        self.warmup_count += 1;
+        if result.success {
+            self.warmup_success_count += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &MethodAccumulator) {
+        if self.call_count() == 0 {
+            self.proxy = other.proxy.clone();
+        }
+        if self.warmup_count == 0 {
+            self.first_call_latency = other.first_call_latency;
+        }
+        self.warmup_count += other.warmup_count;
+        self.warmup_success_count += other.warmup_success_count;
+        self.warm.merge(&other.warm);
+        self.cold.merge(&other.cold);
+        self.warm_latency.merge(&other.warm_latency);
+        self.cold_latency.merge(&other.cold_latency);
+        self.warm_latency_with_retries.merge(&other.warm_latency_with_retries);
+        self.cold_latency_with_retries.merge(&other.cold_latency_with_retries);
+        self.phases.merge(&other.phases);
+        self.transfer.merge(&other.transfer);
+        merge_counts(&mut self.http_status_counts, &other.http_status_counts);
+        merge_counts(&mut self.error_kind_counts, &other.error_kind_counts);
+        merge_counts(&mut self.rpc_error_code_counts, &other.rpc_error_code_counts);
+        if self.assertion_fail_count == 0 {
+            self.assertion_failure = other.assertion_failure.clone();
+        }
+        self.assertion_pass_count += other.assertion_pass_count;
+        self.assertion_fail_count += other.assertion_fail_count;
+    }
+
+    /// 同时存在冷、热连接请求时，主指标（调用次数、成功率和延迟）只统计热连接请求，冷连接请求单独汇总
+    fn to_stats(&self, chain: &str, endpoint: &str, method: &str) -> MethodStats {
+        // 只有冷连接请求时主指标使用冷连接数据
+        let has_warm = self.warm.calls > 0;
+        let (counts, primary, primary_with_retries) = if has_warm {
+            (&self.warm, &self.warm_latency, &self.warm_latency_with_retries)
+        } else {
+            (&self.cold, &self.cold_latency, &self.cold_latency_with_retries)
+        };
+        let latency = primary.summary();
+        let with_retries = primary_with_retries.summary();
+        let cold = self.cold_latency.summary();
+        let transfer = self.transfer.summary();
+
+        // 冷热连接中位数之差，近似建立连接（DNS+TCP+TLS）的开销
+        let connection_setup_ms = if has_warm && self.cold_latency.count() > 0 && primary.count() > 0 {
+            cold.median - latency.median
+        } else {
+            0.0
+        };
+
+        MethodStats {
+            chain: chain.to_string(),
+            endpoint: endpoint.to_string(),
+            method: method.to_string(),
+            call_count: counts.calls,
+            success_count: counts.successes,
+            min_latency: latency.min,
+            max_latency: latency.max,
+            avg_latency: latency.avg,
+            median_latency: latency.median,
+            p95_latency: latency.p95,
+            success_rate: counts.success_rate(),
+            cold_call_count: self.cold.calls,
+            cold_success_count: self.cold.successes,
+            cold_success_rate: self.cold.success_rate(),
+            cold_avg_latency: cold.avg,
+            cold_median_latency: cold.median,
+            cold_p95_latency: cold.p95,
+            connection_setup_ms,
+            phases: self.phases.average(),
+            avg_request_bytes: transfer.avg_request_bytes,
+            avg_response_bytes: transfer.avg_response_bytes,
+            avg_decoded_bytes: transfer.avg_decoded_bytes,
+            avg_throughput_kib_s: transfer.avg_throughput_kib_s,
+            content_encoding: transfer.content_encoding,
+            proxy: self.proxy.clone(),
+            rate_limited_count: counts.rate_limited,
+            invalid_result_count: counts.invalid_results,
+            http_status_counts: format_counts(&self.http_status_counts),
+            error_kind_counts: format_counts(&self.error_kind_counts),
+            rpc_error_code_counts: format_counts(&self.rpc_error_code_counts),
+            first_attempt_success_rate: counts.first_attempt_successes as f64 / counts.calls as f64,
+            retry_count: counts.retries,
+            avg_latency_with_retries: with_retries.avg,
+            p95_latency_with_retries: with_retries.p95,
+            warmup_count: self.warmup_count,
+            warmup_error_count: self.warmup_count - self.warmup_success_count,
+            first_call_latency: self.first_call_latency,
+            assertion_pass_count: self.assertion_pass_count,
+            assertion_fail_count: self.assertion_fail_count,
+            assertion_failure: self.assertion_failure.clone(),
+        }
+    }
+}
+
+/// 同一类错误的累计，只保留第一次出现时的错误信息
+#[derive(Debug, Clone)]
+struct ErrorAccumulator {
+    count: usize,
+    message: String,
+    data: Option<serde_json::Value>,
+}
+
+/// 压缩对比中一种压缩方式的累计
+#[derive(Debug, Clone, Default)]
+struct CompressionAccumulator {
+    call_count: usize,
+    latency: LatencyHistogram,
+    transfer: TransferAccumulator,
+}
+
+/// 按链、端点和方法分组的键
+type MethodKey = (String, String, String);
+/// 错误汇总的键：链、端点、方法、错误分类、HTTP 状态码和 JSON-RPC 错误码
+type ErrorKey = (String, String, String, ErrorKind, Option<u16>, Option<i64>);
+
+/// 流式统计：逐个记录调用结果，不保存结果本身，内存占用与调用次数无关
+///
+/// 主报告和错误汇总只统计使用主压缩方式的请求，压缩对比模式下额外发出的请求只计入压缩对比报告。
+#[derive(Debug, Clone)]
+pub struct StatsCollector {
+    primary_compression: Compression,
+    methods: HashMap<MethodKey, MethodAccumulator>,
+    errors: HashMap<ErrorKey, ErrorAccumulator>,
+    compression: HashMap<(String, String, String, Compression), CompressionAccumulator>,
+}
+
+impl StatsCollector {
+    pub fn new(primary_compression: Compression) -> Self {
+        Self {
+            primary_compression,
+            methods: HashMap::new(),
+            errors: HashMap::new(),
+            compression: HashMap::new(),
+        }
+    }
+
+    /// 记录一个调用结果
+    pub fn record(&mut self, result: &RpcResult) {
+        if result.connection_type.is_http() {
+            let key = (result.chain.clone(), result.endpoint.clone(), result.method.clone(), result.compression);
+            let entry = self.compression.entry(key).or_default();
+            entry.call_count += 1;
+            if result.success {
+                entry.latency.record(result.latency_ms);
+                entry.transfer.record(result);
+            }
+        }
+
+        if result.compression != self.primary_compression {
+            return;
+        }
+
+        let key = (result.chain.clone(), result.endpoint.clone(), result.method.clone());
+        self.methods.entry(key).or_default().record(result);
+
+        if let Some(kind) = result.error_kind {
+            let key = (
+                result.chain.clone(),
+                result.endpoint.clone(),
+                result.method.clone(),
+                kind,
+                result.http_status,
+                result.rpc_error_code,
+            );
+            self.errors
+                .entry(key)
+                .or_insert_with(|| ErrorAccumulator {
+                    count: 0,
+                    message: result.error.clone().unwrap_or_default(),
+                    data: result.rpc_error_data.clone(),
+                })
+                .count += 1;
+        }
+    }
+
+    /// 记录一个预热请求的结果，只计入首次调用延迟和预热失败次数
+    pub fn record_warmup(&mut self, result: &RpcResult) {
+        let key = (result.chain.clone(), result.endpoint.clone(), result.method.clone());
+        self.methods.entry(key).or_default().record_warmup(result);
+    }
+
+    /// 合并另一个收集器的统计（例如并行测试的其他端点）
+    pub fn merge(&mut self, other: &StatsCollector) {
+        for (key, accumulator) in &other.methods {
+            self.methods.entry(key.clone()).or_default().merge(accumulator);
+        }
+        for (key, accumulator) in &other.errors {
+            self.errors
+                .entry(key.clone())
+                .and_modify(|e| e.count += accumulator.count)
+                .or_insert_with(|| accumulator.clone());
+        }
+        for (key, accumulator) in &other.compression {
+            let entry = self.compression.entry(key.clone()).or_default();
+            entry.call_count += accumulator.call_count;
+            entry.latency.merge(&accumulator.latency);
+            entry.transfer.merge(&accumulator.transfer);
+        }
+    }
+
+    /// 各方法的统计指标，按链排序，同一链内按成功率降序、平均延迟升序排序
+    ///
+    /// 只完成了预热、没有正式调用的方法（例如到达截止时间）不包括在内。
+    pub fn method_stats(&self) -> Vec<MethodStats> {
+        self.methods
+            .iter()
+            .filter(|(_, accumulator)| accumulator.call_count() > 0)
+            .map(|((chain, endpoint, method), accumulator)| accumulator.to_stats(chain, endpoint, method))
+            .sorted_by(|a, b| {
+                a.chain.cmp(&b.chain).then_with(|| {
+                    // 先按成功率排序（降序）
+                    b.success_rate.partial_cmp(&a.success_rate).unwrap_or(std::cmp::Ordering::Equal)
+                    // 如果成功率相同，按平均延迟排序（升序）
+                    .then_with(|| a.avg_latency.partial_cmp(&b.avg_latency).unwrap_or(std::cmp::Ordering::Equal))
+                })
             })
-        })
-        .collect()
+            .collect()
+    }
+
+    /// 按端点、方法、错误分类、HTTP 状态码和错误码汇总失败的调用
+    pub fn error_stats(&self) -> Vec<ErrorStats> {
+        self.errors
+            .iter()
+            .map(|((chain, endpoint, method, kind, http_status, rpc_error_code), accumulator)| ErrorStats {
+                chain: chain.clone(),
+                endpoint: endpoint.clone(),
+                method: method.clone(),
+                kind: *kind,
+                http_status: *http_status,
+                rpc_error_code: *rpc_error_code,
+                count: accumulator.count,
+                message: accumulator.message.clone(),
+                data: accumulator.data.clone(),
+            })
+            .sorted_by(|a, b| {
+                a.chain.cmp(&b.chain)
+                    .then_with(|| b.count.cmp(&a.count))
+                    .then_with(|| a.method.cmp(&b.method))
+            })
+            .collect()
+    }
+
+    /// 按链、端点、方法和压缩方式汇总压缩对比结果，只统计 HTTP 请求
+    pub fn compression_stats(&self) -> Vec<CompressionStats> {
+        self.compression
+            .iter()
+            .map(|((chain, endpoint, method, compression), accumulator)| {
+                let transfer = accumulator.transfer.summary();
+                CompressionStats {
+                    chain: chain.clone(),
+                    endpoint: endpoint.clone(),
+                    method: method.clone(),
+                    compression: *compression,
+                    call_count: accumulator.call_count,
+                    success_count: accumulator.latency.count() as usize,
+                    median_latency: accumulator.latency.summary().median,
+                    avg_response_bytes: transfer.avg_response_bytes,
+                    avg_decoded_bytes: transfer.avg_decoded_bytes,
+                    compression_ratio: if transfer.avg_decoded_bytes > 0.0 {
+                        transfer.avg_response_bytes / transfer.avg_decoded_bytes
+                    } else {
+                        0.0
+                    },
+                    avg_throughput_kib_s: transfer.avg_throughput_kib_s,
+                    content_encoding: transfer.content_encoding,
+                }
+            })
+            .sorted_by(|a, b| {
+                a.chain
+                    .cmp(&b.chain)
+                    .then_with(|| a.method.cmp(&b.method))
+                    .then_with(|| a.compression.name().cmp(b.compression.name()))
+            })
+            .collect()
+    }
+}
+
+fn merge_counts<K: Ord + Clone>(counts: &mut BTreeMap<K, usize>, other: &BTreeMap<K, usize>) {
+    for (key, count) in other {
+        *counts.entry(key.clone()).or_default() += count;
+    }
+}
+
+/// 按值排序格式化为 `值:次数`，以空格分隔
+fn format_counts<K: std::fmt::Display>(counts: &BTreeMap<K, usize>) -> String {
+    counts
+        .iter()
+        .map(|(value, count)| format!("{}:{}", value, count))
+        .join(" ")
 }
 
-/// 将统计数据写入 CSV 文件
-pub fn write_to_csv(stats: &[MethodStats], output_path: &Path) -> Result<()> {
+/// 在输出文件名后附加后缀，用于生成附属报告 (例如 rpc-metrics-subscriptions.csv)
+pub fn suffixed_path(output_path: &Path, suffix: &str) -> PathBuf {
+    let stem = output_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "rpc-metrics".to_string());
+    let extension = output_path
+        .extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "csv".to_string());
+    output_path.with_file_name(format!("{}-{}.{}", stem, suffix, extension))
+}
+
+/// 将统计数据写入 CSV 文件，每行记录本次测试使用的调度方式
+pub fn write_to_csv(stats: &[MethodStats], schedule: &str, output_path: &Path) -> Result<()> {
     let file = File::create(output_path)?;
     let mut wtr = csv::Writer::from_writer(file);
     
     // 写入 CSV 头
-    wtr.write_record(&[
+    wtr.write_record([
         "chain",
         "endpoint",
         "method",
@@ -148,11 +741,49 @@ pub fn write_to_csv(stats: &[MethodStats], output_path: &Path) -> Result<()> {
         "median_latency_ms",
         "p95_latency_ms",
         "success_rate_percent",
+        "cold_call_count",
+        "cold_success_count",
+        "cold_success_rate_percent",
+        "cold_avg_latency_ms",
+        "cold_median_latency_ms",
+        "cold_p95_latency_ms",
+        "connection_setup_ms",
+        "dns_ms",
+        "connect_ms",
+        "tls_ms",
+        "request_write_ms",
+        "ttfb_ms",
+        "body_ms",
+        "avg_request_bytes",
+        "avg_response_bytes",
+        "avg_decoded_bytes",
+        "content_encoding",
+        "throughput_kib_s",
+        "proxy",
+        "rate_limited_count",
+        "rate_limit_percent",
+        "error_count",
+        "invalid_result_count",
+        "invalid_result_percent",
+        "http_status_counts",
+        "error_kind_counts",
+        "rpc_error_code_counts",
+        "first_attempt_success_percent",
+        "retry_count",
+        "avg_latency_with_retries_ms",
+        "p95_latency_with_retries_ms",
+        "warmup_count",
+        "warmup_error_count",
+        "first_call_latency_ms",
+        "schedule",
+        "assertion_pass_count",
+        "assertion_fail_count",
+        "assertion_failure",
     ])?;
     
     // 写入每个方法的统计数据
     for stat in stats {
-        wtr.write_record(&[
+        wtr.write_record([
             &stat.chain,
             &stat.endpoint,
             &stat.method,
@@ -164,6 +795,44 @@ pub fn write_to_csv(stats: &[MethodStats], output_path: &Path) -> Result<()> {
             &format!("{:.2}", stat.median_latency),
             &format!("{:.2}", stat.p95_latency),
             &format!("{:.2}", stat.success_rate * 100.0),
+            &stat.cold_call_count.to_string(),
+            &stat.cold_success_count.to_string(),
+            &format!("{:.2}", stat.cold_success_rate * 100.0),
+            &format!("{:.2}", stat.cold_avg_latency),
+            &format!("{:.2}", stat.cold_median_latency),
+            &format!("{:.2}", stat.cold_p95_latency),
+            &format!("{:.2}", stat.connection_setup_ms),
+            &format_phase(stat.phases.dns_ms),
+            &format_phase(stat.phases.connect_ms),
+            &format_phase(stat.phases.tls_ms),
+            &format_phase(stat.phases.request_write_ms),
+            &format_phase(stat.phases.ttfb_ms),
+            &format_phase(stat.phases.body_ms),
+            &format!("{:.0}", stat.avg_request_bytes),
+            &format!("{:.0}", stat.avg_response_bytes),
+            &format!("{:.0}", stat.avg_decoded_bytes),
+            &stat.content_encoding,
+            &format!("{:.2}", stat.avg_throughput_kib_s),
+            &stat.proxy,
+            &stat.rate_limited_count.to_string(),
+            &format!("{:.2}", stat.rate_limited_count as f64 / stat.call_count as f64 * 100.0),
+            &stat.error_count().to_string(),
+            &stat.invalid_result_count.to_string(),
+            &format!("{:.2}", stat.invalid_result_count as f64 / stat.call_count as f64 * 100.0),
+            &stat.http_status_counts,
+            &stat.error_kind_counts,
+            &stat.rpc_error_code_counts,
+            &format!("{:.2}", stat.first_attempt_success_rate * 100.0),
+            &stat.retry_count.to_string(),
+            &format!("{:.2}", stat.avg_latency_with_retries),
+            &format!("{:.2}", stat.p95_latency_with_retries),
+            &stat.warmup_count.to_string(),
+            &stat.warmup_error_count.to_string(),
+            &format_phase(stat.first_call_latency),
+            schedule,
+            &stat.assertion_pass_count.to_string(),
+            &stat.assertion_fail_count.to_string(),
+            &stat.assertion_failure,
         ])?;
     }
     
@@ -171,6 +840,11 @@ pub fn write_to_csv(stats: &[MethodStats], output_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// 格式化阶段耗时，未记录的阶段输出为空
+fn format_phase(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.2}", v)).unwrap_or_default()
+}
+
 /// 在控制台中打印统计数据
 pub fn print_stats(stats: &[MethodStats]) {
     // 创建并格式化表格
@@ -257,4 +931,661 @@ pub fn print_stats(stats: &[MethodStats]) {
     
     // 打印表格
     table.printstd();
-} 
\ No newline at end of file
+} 
+/// 在控制台中打印冷、热连接延迟对比，没有冷连接请求时不输出
+pub fn print_connection_stats(stats: &[MethodStats]) {
+    let cold_stats: Vec<_> = stats.iter().filter(|s| s.cold_call_count > 0).collect();
+    if cold_stats.is_empty() {
+        return;
+    }
+    
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("方法"),
+        Cell::new("冷连接次数"),
+        Cell::new("热连接成功率"),
+        Cell::new("冷连接成功率"),
+        Cell::new("热连接中位数(ms)"),
+        Cell::new("冷连接中位数(ms)"),
+        Cell::new("冷连接P95(ms)"),
+        Cell::new("建连开销(ms)"),
+    ]));
+    
+    for stat in cold_stats {
+        table.add_row(Row::new(vec![
+            Cell::new(&stat.chain),
+            Cell::new(&stat.method),
+            Cell::new(&stat.cold_call_count.to_string()),
+            Cell::new(&format!("{:.2}%", stat.success_rate * 100.0)),
+            Cell::new(&format!("{:.2}%", stat.cold_success_rate * 100.0)),
+            Cell::new(&format!("{:.2}", stat.median_latency)),
+            Cell::new(&format!("{:.2}", stat.cold_median_latency)),
+            Cell::new(&format!("{:.2}", stat.cold_p95_latency)),
+            Cell::new(&format!("{:.2}", stat.connection_setup_ms)),
+        ]));
+    }
+    
+    table.printstd();
+}
+
+/// 在控制台中打印各请求阶段的平均耗时，未记录的阶段显示为 -，没有任何阶段数据时不输出
+pub fn print_phase_stats(stats: &[MethodStats]) {
+    let phase_stats: Vec<_> = stats.iter().filter(|s| s.phases.ttfb_ms.is_some()).collect();
+    if phase_stats.is_empty() {
+        return;
+    }
+    
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("方法"),
+        Cell::new("DNS(ms)"),
+        Cell::new("TCP连接(ms)"),
+        Cell::new("TLS握手(ms)"),
+        Cell::new("发送请求(ms)"),
+        Cell::new("首字节(ms)"),
+        Cell::new("响应体(ms)"),
+    ]));
+    
+    let cell = |value: Option<f64>| Cell::new(&value.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "-".to_string()));
+    
+    for stat in phase_stats {
+        table.add_row(Row::new(vec![
+            Cell::new(&stat.chain),
+            Cell::new(&stat.method),
+            cell(stat.phases.dns_ms),
+            cell(stat.phases.connect_ms),
+            cell(stat.phases.tls_ms),
+            cell(stat.phases.request_write_ms),
+            cell(stat.phases.ttfb_ms),
+            cell(stat.phases.body_ms),
+        ]));
+    }
+    
+    table.printstd();
+}
+
+/// 在控制台中打印每个方法的请求、响应大小和有效吞吐量
+pub fn print_transfer_stats(stats: &[MethodStats]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("方法"),
+        Cell::new("请求(字节)"),
+        Cell::new("响应(字节)"),
+        Cell::new("解压后(字节)"),
+        Cell::new("内容编码"),
+        Cell::new("吞吐量(KiB/s)"),
+    ]));
+    
+    for stat in stats.iter().filter(|s| s.success_count > 0) {
+        table.add_row(Row::new(vec![
+            Cell::new(&stat.chain),
+            Cell::new(&stat.method),
+            Cell::new(&format!("{:.0}", stat.avg_request_bytes)),
+            Cell::new(&format!("{:.0}", stat.avg_response_bytes)),
+            Cell::new(&format!("{:.0}", stat.avg_decoded_bytes)),
+            Cell::new(&stat.content_encoding),
+            Cell::new(&format!("{:.2}", stat.avg_throughput_kib_s)),
+        ]));
+    }
+    
+    table.printstd();
+}
+
+/// 在控制台中打印首次调用（第一个预热请求）延迟与预热后的延迟对比
+pub fn print_warmup_stats(stats: &[MethodStats]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("方法"),
+        Cell::new("预热次数"),
+        Cell::new("预热失败"),
+        Cell::new("首次调用延迟(ms)"),
+        Cell::new("平均延迟(ms)"),
+        Cell::new("最大延迟(ms)"),
+    ]));
+    
+    for stat in stats {
+        let color = if stat.warmup_error_count == 0 { "Fg=Green" } else { "Fg=Red" };
+        table.add_row(Row::new(vec![
+            Cell::new(&stat.chain),
+            Cell::new(&stat.method),
+            Cell::new(&stat.warmup_count.to_string()),
+            Cell::new(&stat.warmup_error_count.to_string()).style_spec(color),
+            Cell::new(&stat.first_call_latency.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "-".to_string())),
+            Cell::new(&format!("{:.2}", stat.avg_latency)),
+            Cell::new(&format!("{:.2}", stat.max_latency)),
+        ]));
+    }
+    
+    table.printstd();
+}
+
+/// 在控制台中打印首次尝试成功率、最终成功率和包含重试的延迟
+pub fn print_retry_stats(stats: &[MethodStats]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("方法"),
+        Cell::new("首次成功率"),
+        Cell::new("最终成功率"),
+        Cell::new("重试次数"),
+        Cell::new("平均延迟(ms)"),
+        Cell::new("含重试平均(ms)"),
+        Cell::new("含重试P95(ms)"),
+    ]));
+    
+    for stat in stats {
+        let color = if stat.retry_count == 0 { "Fg=Green" } else { "Fg=Yellow" };
+        table.add_row(Row::new(vec![
+            Cell::new(&stat.chain),
+            Cell::new(&stat.method),
+            Cell::new(&format!("{:.2}%", stat.first_attempt_success_rate * 100.0)),
+            Cell::new(&format!("{:.2}%", stat.success_rate * 100.0)),
+            Cell::new(&stat.retry_count.to_string()).style_spec(color),
+            Cell::new(&format!("{:.2}", stat.avg_latency)),
+            Cell::new(&format!("{:.2}", stat.avg_latency_with_retries)),
+            Cell::new(&format!("{:.2}", stat.p95_latency_with_retries)),
+        ]));
+    }
+    
+    table.printstd();
+}
+
+/// 在控制台中按端点打印限流次数和限流率，与其他失败分开统计
+pub fn print_rate_limit_stats(stats: &[MethodStats]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("调用次数"),
+        Cell::new("限流次数"),
+        Cell::new("限流率"),
+        Cell::new("其他失败次数"),
+        Cell::new("失败率"),
+    ]));
+    
+    for (chain, chain_stats) in &stats.iter().group_by(|s| &s.chain) {
+        let chain_stats: Vec<_> = chain_stats.collect();
+        let call_count: usize = chain_stats.iter().map(|s| s.call_count).sum();
+        let rate_limited_count: usize = chain_stats.iter().map(|s| s.rate_limited_count).sum();
+        let error_count: usize = chain_stats.iter().map(|s| s.error_count()).sum();
+        let color = if rate_limited_count == 0 { "Fg=Green" } else { "Fg=Yellow" };
+        
+        table.add_row(Row::new(vec![
+            Cell::new(chain),
+            Cell::new(&call_count.to_string()),
+            Cell::new(&rate_limited_count.to_string()).style_spec(color),
+            Cell::new(&format!("{:.2}%", rate_limited_count as f64 / call_count as f64 * 100.0)),
+            Cell::new(&error_count.to_string()),
+            Cell::new(&format!("{:.2}%", error_count as f64 / call_count as f64 * 100.0)),
+        ]));
+    }
+    
+    table.printstd();
+}
+
+/// 在控制台中打印结果未通过校验的方法，与传输错误和节点返回的错误分开统计
+pub fn print_validation_stats(stats: &[MethodStats]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("方法"),
+        Cell::new("调用次数"),
+        Cell::new("校验失败次数"),
+        Cell::new("校验失败率"),
+        Cell::new("其他失败次数"),
+    ]));
+    
+    for stat in stats.iter().filter(|s| s.invalid_result_count > 0) {
+        table.add_row(Row::new(vec![
+            Cell::new(&stat.chain),
+            Cell::new(&stat.method),
+            Cell::new(&stat.call_count.to_string()),
+            Cell::new(&stat.invalid_result_count.to_string()).style_spec("Fg=Red"),
+            Cell::new(&format!("{:.2}%", stat.invalid_result_count as f64 / stat.call_count as f64 * 100.0)),
+            Cell::new(&(stat.error_count() + stat.rate_limited_count).to_string()),
+        ]));
+    }
+    
+    table.printstd();
+}
+
+/// 在控制台中打印各端点检查了期望值断言的方法的通过和失败次数
+pub fn print_assertion_stats(stats: &[MethodStats]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("方法"),
+        Cell::new("通过"),
+        Cell::new("失败"),
+        Cell::new("未通过的断言"),
+    ]));
+    
+    for stat in stats.iter().filter(|s| s.assertion_pass_count + s.assertion_fail_count > 0) {
+        let color = if stat.assertion_fail_count > 0 { "Fg=Red" } else { "Fg=Green" };
+        table.add_row(Row::new(vec![
+            Cell::new(&stat.chain),
+            Cell::new(&stat.method),
+            Cell::new(&stat.assertion_pass_count.to_string()),
+            Cell::new(&stat.assertion_fail_count.to_string()).style_spec(color),
+            Cell::new(&stat.assertion_failure),
+        ]));
+    }
+    
+    table.printstd();
+}
+
+/// 同一端点、方法下同一类错误的汇总
+#[derive(Debug, Clone)]
+pub struct ErrorStats {
+    /// 链名称
+    pub chain: String,
+    /// 端点URL
+    pub endpoint: String,
+    /// 方法名称
+    pub method: String,
+    /// 错误分类
+    pub kind: ErrorKind,
+    /// HTTP 状态码
+    pub http_status: Option<u16>,
+    /// JSON-RPC 错误码
+    pub rpc_error_code: Option<i64>,
+    /// 出现次数
+    pub count: usize,
+    /// 第一次出现时的错误信息
+    pub message: String,
+    /// 第一次出现时的 JSON-RPC `error.data`
+    pub data: Option<serde_json::Value>,
+}
+
+/// 将错误汇总写入 CSV 文件
+pub fn write_errors_to_csv(stats: &[ErrorStats], output_path: &Path) -> Result<()> {
+    let file = File::create(output_path)?;
+    let mut wtr = csv::Writer::from_writer(file);
+    
+    wtr.write_record([
+        "chain",
+        "endpoint",
+        "method",
+        "error_kind",
+        "http_status",
+        "rpc_error_code",
+        "count",
+        "message",
+        "rpc_error_data",
+    ])?;
+    
+    for stat in stats {
+        wtr.write_record([
+            &stat.chain,
+            &stat.endpoint,
+            &stat.method,
+            stat.kind.name(),
+            &stat.http_status.map(|s| s.to_string()).unwrap_or_default(),
+            &stat.rpc_error_code.map(|c| c.to_string()).unwrap_or_default(),
+            &stat.count.to_string(),
+            &stat.message,
+            &stat.data.as_ref().map(|d| d.to_string()).unwrap_or_default(),
+        ])?;
+    }
+    
+    wtr.flush()?;
+    Ok(())
+}
+
+/// 在控制台中打印错误汇总
+pub fn print_error_stats(stats: &[ErrorStats]) {
+    const MAX_MESSAGE_CHARS: usize = 60;
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("方法"),
+        Cell::new("错误类型"),
+        Cell::new("HTTP状态"),
+        Cell::new("错误码"),
+        Cell::new("次数"),
+        Cell::new("错误信息"),
+    ]));
+    
+    for stat in stats {
+        let color = if stat.kind == ErrorKind::RateLimited { "Fg=Yellow" } else { "Fg=Red" };
+        let mut message: String = stat.message.chars().take(MAX_MESSAGE_CHARS).collect();
+        if message.len() < stat.message.len() {
+            message.push_str("...");
+        }
+        
+        table.add_row(Row::new(vec![
+            Cell::new(&stat.chain),
+            Cell::new(&stat.method),
+            Cell::new(stat.kind.name()).style_spec(color),
+            Cell::new(&stat.http_status.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string())),
+            Cell::new(&stat.rpc_error_code.map(|c| c.to_string()).unwrap_or_else(|| "-".to_string())),
+            Cell::new(&stat.count.to_string()),
+            Cell::new(&message),
+        ]));
+    }
+    
+    table.printstd();
+}
+
+/// 同一端点、方法在某种压缩协商方式下的统计
+#[derive(Debug, Clone)]
+pub struct CompressionStats {
+    /// 链名称
+    pub chain: String,
+    /// 端点URL
+    pub endpoint: String,
+    /// 方法名称
+    pub method: String,
+    /// 请求的压缩方式
+    pub compression: Compression,
+    /// 调用次数
+    pub call_count: usize,
+    /// 成功调用次数
+    pub success_count: usize,
+    /// 中位数延迟（毫秒）
+    pub median_latency: f64,
+    /// 平均响应体字节数（线路上）
+    pub avg_response_bytes: f64,
+    /// 平均解压后响应体字节数
+    pub avg_decoded_bytes: f64,
+    /// 压缩率：线路字节数 / 解压后字节数
+    pub compression_ratio: f64,
+    /// 平均有效吞吐量（KiB/s）
+    pub avg_throughput_kib_s: f64,
+    /// 节点实际返回的 Content-Encoding
+    pub content_encoding: String,
+}
+
+/// 将压缩对比结果写入 CSV 文件
+pub fn write_compression_to_csv(stats: &[CompressionStats], output_path: &Path) -> Result<()> {
+    let file = File::create(output_path)?;
+    let mut wtr = csv::Writer::from_writer(file);
+    
+    wtr.write_record([
+        "chain",
+        "endpoint",
+        "method",
+        "compression",
+        "call_count",
+        "success_count",
+        "median_latency_ms",
+        "avg_response_bytes",
+        "avg_decoded_bytes",
+        "compression_ratio",
+        "content_encoding",
+        "throughput_kib_s",
+    ])?;
+    
+    for stat in stats {
+        wtr.write_record([
+            &stat.chain,
+            &stat.endpoint,
+            &stat.method,
+            stat.compression.name(),
+            &stat.call_count.to_string(),
+            &stat.success_count.to_string(),
+            &format!("{:.2}", stat.median_latency),
+            &format!("{:.0}", stat.avg_response_bytes),
+            &format!("{:.0}", stat.avg_decoded_bytes),
+            &format!("{:.3}", stat.compression_ratio),
+            &stat.content_encoding,
+            &format!("{:.2}", stat.avg_throughput_kib_s),
+        ])?;
+    }
+    
+    wtr.flush()?;
+    Ok(())
+}
+
+/// 在控制台中打印压缩对比结果
+pub fn print_compression_stats(stats: &[CompressionStats]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("方法"),
+        Cell::new("压缩方式"),
+        Cell::new("成功次数"),
+        Cell::new("中位数延迟(ms)"),
+        Cell::new("响应(字节)"),
+        Cell::new("解压后(字节)"),
+        Cell::new("压缩率"),
+        Cell::new("内容编码"),
+        Cell::new("吞吐量(KiB/s)"),
+    ]));
+    
+    for stat in stats {
+        table.add_row(Row::new(vec![
+            Cell::new(&stat.chain),
+            Cell::new(&stat.method),
+            Cell::new(stat.compression.name()),
+            Cell::new(&format!("{}/{}", stat.success_count, stat.call_count)),
+            Cell::new(&format!("{:.2}", stat.median_latency)),
+            Cell::new(&format!("{:.0}", stat.avg_response_bytes)),
+            Cell::new(&format!("{:.0}", stat.avg_decoded_bytes)),
+            Cell::new(&format!("{:.3}", stat.compression_ratio)),
+            Cell::new(&stat.content_encoding),
+            Cell::new(&format!("{:.2}", stat.avg_throughput_kib_s)),
+        ]));
+    }
+    
+    table.printstd();
+}
+
+/// 一个 WebSocket 端点的重连统计
+#[derive(Debug, Clone)]
+pub struct ReconnectStats {
+    /// 链名称
+    pub chain: String,
+    /// 端点URL
+    pub endpoint: String,
+    /// 重连次数
+    pub reconnect_count: usize,
+    /// 重连成功次数
+    pub success_count: usize,
+    /// 连接尝试总次数
+    pub attempt_count: u32,
+    /// 最小重连耗时（毫秒）
+    pub min_duration: f64,
+    /// 最大重连耗时（毫秒）
+    pub max_duration: f64,
+    /// 平均重连耗时（毫秒）
+    pub avg_duration: f64,
+    /// 最近一次重连的时间
+    pub last_reconnect: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// 按端点汇总 WebSocket 重连事件，没有重连的端点同样列出
+pub fn calculate_reconnect_stats(chains: &[Chain], events: &[ReconnectEvent]) -> Vec<ReconnectStats> {
+    chains
+        .iter()
+        .filter(|c| c.connection_type == ConnectionType::WebSocket)
+        .map(|chain| {
+            let endpoint_events: Vec<_> = events.iter().filter(|e| e.endpoint == chain.rpc_url).collect();
+            let durations: Vec<f64> = endpoint_events.iter().map(|e| e.duration_ms).collect();
+            let (min_duration, max_duration, avg_duration) = if durations.is_empty() {
+                (0.0, 0.0, 0.0)
+            } else {
+                (
+                    durations.iter().copied().fold(f64::INFINITY, f64::min),
+                    durations.iter().copied().fold(0.0, f64::max),
+                    durations.iter().sum::<f64>() / durations.len() as f64,
+                )
+            };
+            
+            ReconnectStats {
+                chain: chain.name.clone(),
+                endpoint: chain.rpc_url.clone(),
+                reconnect_count: endpoint_events.len(),
+                success_count: endpoint_events.iter().filter(|e| e.success).count(),
+                attempt_count: endpoint_events.iter().map(|e| e.attempts).sum(),
+                min_duration,
+                max_duration,
+                avg_duration,
+                last_reconnect: endpoint_events.iter().map(|e| e.timestamp).max(),
+            }
+        })
+        .collect()
+}
+
+/// 将重连统计写入 CSV 文件
+pub fn write_reconnects_to_csv(stats: &[ReconnectStats], output_path: &Path) -> Result<()> {
+    let file = File::create(output_path)?;
+    let mut wtr = csv::Writer::from_writer(file);
+    
+    wtr.write_record([
+        "chain",
+        "endpoint",
+        "reconnect_count",
+        "reconnect_success_count",
+        "attempt_count",
+        "min_reconnect_ms",
+        "max_reconnect_ms",
+        "avg_reconnect_ms",
+        "last_reconnect_at",
+    ])?;
+    
+    for stat in stats {
+        wtr.write_record([
+            &stat.chain,
+            &stat.endpoint,
+            &stat.reconnect_count.to_string(),
+            &stat.success_count.to_string(),
+            &stat.attempt_count.to_string(),
+            &format!("{:.2}", stat.min_duration),
+            &format!("{:.2}", stat.max_duration),
+            &format!("{:.2}", stat.avg_duration),
+            &stat.last_reconnect.map(|t| t.to_rfc3339()).unwrap_or_default(),
+        ])?;
+    }
+    
+    wtr.flush()?;
+    Ok(())
+}
+
+/// 在控制台中打印重连统计
+pub fn print_reconnect_stats(stats: &[ReconnectStats]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("重连次数"),
+        Cell::new("成功次数"),
+        Cell::new("尝试次数"),
+        Cell::new("最小耗时(ms)"),
+        Cell::new("最大耗时(ms)"),
+        Cell::new("平均耗时(ms)"),
+    ]));
+    
+    for stat in stats {
+        let color = if stat.reconnect_count == 0 {
+            "Fg=Green"
+        } else if stat.success_count == stat.reconnect_count {
+            "Fg=Yellow"
+        } else {
+            "Fg=Red"
+        };
+        
+        table.add_row(Row::new(vec![
+            Cell::new(&stat.chain),
+            Cell::new(&stat.reconnect_count.to_string()).style_spec(color),
+            Cell::new(&stat.success_count.to_string()),
+            Cell::new(&stat.attempt_count.to_string()),
+            Cell::new(&format!("{:.2}", stat.min_duration)),
+            Cell::new(&format!("{:.2}", stat.max_duration)),
+            Cell::new(&format!("{:.2}", stat.avg_duration)),
+        ]));
+    }
+    
+    table.printstd();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(cold: bool, error_kind: Option<ErrorKind>) -> RpcResult {
+        RpcResult {
+            chain: "ETH-HTTP".to_string(),
+            endpoint: "https://eth.example".to_string(),
+            connection_type: ConnectionType::Http,
+            method: "eth_blockNumber".to_string(),
+            success: error_kind.is_none(),
+            latency_ms: if cold { 50.0 } else { 10.0 },
+            error: error_kind.map(|kind| kind.name().to_string()),
+            cold,
+            phases: PhaseTimings::default(),
+            compression: Compression::Identity,
+            transfer: Default::default(),
+            proxy: String::new(),
+            error_kind,
+            http_status: None,
+            rpc_error_code: None,
+            rpc_error_data: None,
+            retry_after: None,
+            attempts: 1,
+            first_attempt_success: error_kind.is_none(),
+            latency_with_retries_ms: if cold { 50.0 } else { 10.0 },
+            assertions_checked: 0,
+            assertion_failures: Vec::new(),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn cold_failures_do_not_lower_warm_success_rate() {
+        let mut collector = StatsCollector::new(Compression::Identity);
+        for _ in 0..4 {
+            collector.record(&result(false, None));
+        }
+        collector.record(&result(true, None));
+        for _ in 0..3 {
+            collector.record(&result(true, Some(ErrorKind::Tls)));
+        }
+
+        let stats = &collector.method_stats()[0];
+        assert_eq!(stats.call_count, 4);
+        assert_eq!(stats.success_rate, 1.0);
+        assert_eq!(stats.first_attempt_success_rate, 1.0);
+        assert_eq!(stats.error_count(), 0);
+        assert_eq!(stats.cold_call_count, 4);
+        assert_eq!(stats.cold_success_count, 1);
+        assert_eq!(stats.cold_success_rate, 0.25);
+    }
+
+    #[test]
+    fn cold_only_calls_are_the_primary_metrics() {
+        let mut collector = StatsCollector::new(Compression::Identity);
+        collector.record(&result(true, None));
+        collector.record(&result(true, Some(ErrorKind::Timeout)));
+
+        let stats = &collector.method_stats()[0];
+        assert_eq!(stats.call_count, 2);
+        assert_eq!(stats.success_rate, 0.5);
+        assert_eq!(stats.error_count(), 1);
+        assert_eq!(stats.median_latency, stats.cold_median_latency);
+    }
+}
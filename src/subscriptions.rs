@@ -0,0 +1,547 @@
+use crate::chains::{Chain, ConnectionType};
+use crate::rpc::{Config, ReconnectEvent, WebSocketManager, WsSubscription};
+use crate::validate::parse_quantity;
+use anyhow::Result;
+use futures::future;
+use itertools::Itertools;
+use log::{debug, error, info, warn};
+use prettytable::{format, Cell, Row, Table};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// 订阅类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionKind {
+    /// 新区块头
+    NewHeads,
+    /// 事件日志
+    Logs,
+    /// 待处理交易
+    NewPendingTransactions,
+}
+
+impl SubscriptionKind {
+    /// eth_subscribe 使用的订阅名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            SubscriptionKind::NewHeads => "newHeads",
+            SubscriptionKind::Logs => "logs",
+            SubscriptionKind::NewPendingTransactions => "newPendingTransactions",
+        }
+    }
+
+    /// 构造 eth_subscribe 参数
+    fn params(&self, logs_filter: &Value) -> Vec<Value> {
+        match self {
+            SubscriptionKind::Logs => vec![json!(self.name()), logs_filter.clone()],
+            _ => vec![json!(self.name())],
+        }
+    }
+
+    /// 从通知中提取去重键和所属区块号
+    ///
+    /// 被重组撤销 (`removed: true`) 的日志返回 None，不计入完整性统计。
+    fn delivery_key(&self, result: &Value) -> Option<(String, Option<u64>)> {
+        match self {
+            SubscriptionKind::NewHeads => {
+                let hash = result.get("hash")?.as_str()?.to_lowercase();
+                Some((hash, result.get("number").and_then(parse_quantity)))
+            }
+            SubscriptionKind::Logs => {
+                if result.get("removed").and_then(|r| r.as_bool()) == Some(true) {
+                    return None;
+                }
+                let block_hash = result.get("blockHash")?.as_str()?.to_lowercase();
+                let log_index = result.get("logIndex")?.as_str()?.to_lowercase();
+                Some((
+                    format!("{}:{}", block_hash, log_index),
+                    result.get("blockNumber").and_then(parse_quantity),
+                ))
+            }
+            SubscriptionKind::NewPendingTransactions => {
+                // 部分节点推送完整交易对象而不是交易哈希
+                let hash = result
+                    .as_str()
+                    .or_else(|| result.get("hash").and_then(|h| h.as_str()))?;
+                Some((hash.to_lowercase(), None))
+            }
+        }
+    }
+}
+
+/// 解析逗号分隔的订阅类型列表
+pub fn parse_kinds(kinds_str: &str) -> Result<Vec<SubscriptionKind>> {
+    kinds_str
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| match s {
+            "newHeads" => Ok(SubscriptionKind::NewHeads),
+            "logs" => Ok(SubscriptionKind::Logs),
+            "newPendingTransactions" => Ok(SubscriptionKind::NewPendingTransactions),
+            _ => anyhow::bail!(
+                "无效的订阅类型: {}。支持的类型: newHeads, logs, newPendingTransactions",
+                s
+            ),
+        })
+        .collect()
+}
+
+/// 单个端点在一次订阅中收到的数据
+struct Capture {
+    chain: String,
+    network: String,
+    endpoint: String,
+    proxy: String,
+    /// 收到的通知总数
+    notifications: usize,
+    /// 每个去重键收到的次数
+    deliveries: HashMap<String, (usize, Option<u64>)>,
+    /// 实际订阅时长（秒）
+    elapsed_secs: f64,
+    /// 断线后的重新订阅事件
+    reconnects: Vec<ReconnectEvent>,
+    /// 订阅失败或中途断开时的错误信息
+    error: Option<String>,
+}
+
+impl Capture {
+    /// 收到过数据的区块号集合
+    fn blocks(&self) -> BTreeSet<u64> {
+        self.deliveries.values().filter_map(|(_, block)| *block).collect()
+    }
+}
+
+/// 单个端点的订阅统计指标
+#[derive(Debug, Clone)]
+pub struct SubscriptionStats {
+    /// 链名称
+    pub chain: String,
+    /// 端点URL
+    pub endpoint: String,
+    /// 使用的代理（隐去密码），直连时为空
+    pub proxy: String,
+    /// 订阅类型
+    pub kind: SubscriptionKind,
+    /// 收到的通知总数
+    pub notifications: usize,
+    /// 每秒通知数
+    pub notifications_per_sec: f64,
+    /// 去重后的通知数
+    pub unique_count: usize,
+    /// 重复推送次数
+    pub duplicate_count: usize,
+    /// 参考集合大小（同网络所有端点的并集）
+    pub reference_count: usize,
+    /// 完整性（覆盖参考集合的比例）
+    pub completeness: f64,
+    /// 其他端点收到数据而本端点缺失的区块数
+    pub gap_blocks: usize,
+    /// 断线后重新订阅的次数
+    pub reconnects: usize,
+    /// 错误信息
+    pub error: Option<String>,
+}
+
+/// 在一个端点上订阅并收集指定时长内的通知
+async fn capture(
+    ws_manager: &WebSocketManager,
+    chain: &Chain,
+    kind: SubscriptionKind,
+    logs_filter: &Value,
+    duration: Duration,
+) -> Capture {
+    let mut capture = Capture {
+        chain: chain.name.clone(),
+        network: chain.network().to_string(),
+        endpoint: chain.rpc_url.clone(),
+        proxy: chain.proxy_label(),
+        notifications: 0,
+        deliveries: HashMap::new(),
+        elapsed_secs: 0.0,
+        reconnects: Vec::new(),
+        error: None,
+    };
+
+    let mut subscription = match ws_manager.subscribe(chain, &kind.params(logs_filter)).await {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            error!("{} 订阅 {} 失败: {}", chain.name, kind.name(), e);
+            capture.error = Some(e.to_string());
+            return capture;
+        }
+    };
+
+    let start = Instant::now();
+    let deadline = start + duration;
+
+    loop {
+        match subscription.next_notification(deadline).await {
+            Ok(Some(result)) => {
+                capture.notifications += 1;
+                if let Some((key, block)) = kind.delivery_key(&result) {
+                    capture.deliveries.entry(key).or_insert((0, block)).0 += 1;
+                }
+            }
+            Ok(None) => break,
+            Err(e) => {
+                warn!("{} 的 {} 订阅中断: {}", chain.name, kind.name(), e);
+                let (resubscribed, event) = resubscribe(ws_manager, chain, kind, logs_filter, deadline).await;
+                // 到达截止时间而没有尝试重新订阅时不记录重连事件
+                if event.attempts > 0 {
+                    capture.reconnects.push(event);
+                }
+                match resubscribed {
+                    Some(new_subscription) => subscription = new_subscription,
+                    None => {
+                        error!("{} 的 {} 订阅无法恢复: {}", chain.name, kind.name(), e);
+                        capture.error = Some(e.to_string());
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    capture.elapsed_secs = start.elapsed().as_secs_f64();
+    subscription.unsubscribe().await;
+
+    debug!(
+        "{} {} 订阅结束: {} 条通知, {:.1} 秒",
+        chain.name,
+        kind.name(),
+        capture.notifications,
+        capture.elapsed_secs
+    );
+
+    capture
+}
+
+/// 订阅中断后按退避策略重新订阅，超过重连次数或截止时间时订阅为 None，同时返回重连事件
+async fn resubscribe(
+    ws_manager: &WebSocketManager,
+    chain: &Chain,
+    kind: SubscriptionKind,
+    logs_filter: &Value,
+    deadline: Instant,
+) -> (Option<WsSubscription>, ReconnectEvent) {
+    let config = ws_manager.config();
+    let timestamp = chrono::Utc::now();
+    let start = Instant::now();
+    let mut attempts = 0;
+    let mut subscription = None;
+    for attempt in 1..=config.ws_reconnect_attempts.max(1) {
+        tokio::time::sleep(config.reconnect_backoff(attempt)).await;
+        if Instant::now() >= deadline {
+            break;
+        }
+        attempts = attempt;
+        match ws_manager.subscribe(chain, &kind.params(logs_filter)).await {
+            Ok(new_subscription) => {
+                subscription = Some(new_subscription);
+                break;
+            }
+            Err(e) => warn!("{} 重新订阅 {} 失败 ({}): {}", chain.name, kind.name(), attempt, e),
+        }
+    }
+    let event = ReconnectEvent {
+        endpoint: chain.rpc_url.clone(),
+        timestamp,
+        attempts,
+        duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+        success: subscription.is_some(),
+    };
+    (subscription, event)
+}
+
+/// 以同网络所有端点的并集为参考，计算每个端点的统计指标
+fn score_captures(kind: SubscriptionKind, captures: &[Capture]) -> Vec<SubscriptionStats> {
+    let mut stats = Vec::with_capacity(captures.len());
+
+    for (_, group) in &captures.iter().group_by(|c| &c.network) {
+        let group: Vec<&Capture> = group.collect();
+
+        // 基于区块的订阅从所有端点都已开始接收的区块起比较，避免订阅建立时刻的差异；
+        // 所有端点同时结束订阅，终点取最高的区块，中途停止推送的端点缺失的尾部照样计入
+        let block_ranges: Vec<(u64, u64)> = group
+            .iter()
+            .filter_map(|c| {
+                let blocks = c.blocks();
+                Some((*blocks.first()?, *blocks.last()?))
+            })
+            .collect();
+        let window = if block_ranges.is_empty() {
+            None
+        } else {
+            let low = block_ranges.iter().map(|r| r.0).max().unwrap();
+            let high = block_ranges.iter().map(|r| r.1).max().unwrap();
+            Some((low, high))
+        };
+        let in_window = |block: &Option<u64>| match (window, block) {
+            (Some((low, high)), Some(block)) => *block >= low && *block <= high,
+            (None, _) | (_, None) => true,
+        };
+
+        let reference: HashSet<&String> = group
+            .iter()
+            .flat_map(|c| c.deliveries.iter())
+            .filter(|(_, (_, block))| in_window(block))
+            .map(|(key, _)| key)
+            .collect();
+        let reference_blocks: BTreeSet<u64> = group
+            .iter()
+            .flat_map(|c| c.blocks())
+            .filter(|block| in_window(&Some(*block)))
+            .collect();
+
+        for capture in group {
+            let unique_count = capture.deliveries.len();
+            let duplicate_count = capture
+                .deliveries
+                .values()
+                .map(|(count, _)| count - 1)
+                .sum();
+            let covered = capture
+                .deliveries
+                .iter()
+                .filter(|(key, (_, block))| in_window(block) && reference.contains(key))
+                .count();
+            let completeness = if reference.is_empty() {
+                0.0
+            } else {
+                covered as f64 / reference.len() as f64
+            };
+            let own_blocks = capture.blocks();
+            let gap_blocks = reference_blocks.difference(&own_blocks).count();
+            let notifications_per_sec = if capture.elapsed_secs > 0.0 {
+                capture.notifications as f64 / capture.elapsed_secs
+            } else {
+                0.0
+            };
+
+            stats.push(SubscriptionStats {
+                chain: capture.chain.clone(),
+                endpoint: capture.endpoint.clone(),
+                proxy: capture.proxy.clone(),
+                kind,
+                notifications: capture.notifications,
+                notifications_per_sec,
+                unique_count,
+                duplicate_count,
+                reference_count: reference.len(),
+                completeness,
+                gap_blocks,
+                reconnects: capture.reconnects.len(),
+                error: capture.error.clone(),
+            });
+        }
+    }
+
+    stats
+}
+
+/// 一次订阅测试的输出
+pub struct SubscriptionRun {
+    /// 每个端点、每种订阅的统计指标
+    pub stats: Vec<SubscriptionStats>,
+    /// 订阅中断后的重新订阅事件
+    pub reconnects: Vec<ReconnectEvent>,
+}
+
+/// 对所有 WebSocket 端点执行订阅吞吐量测试
+///
+/// 同一订阅类型在所有端点上同时进行，以便按网络比较推送的完整性。
+pub async fn test_subscriptions(
+    chains: &[Chain],
+    kinds: &[SubscriptionKind],
+    logs_filter: &Value,
+    duration: Duration,
+    config: Config,
+) -> SubscriptionRun {
+    let ws_manager = WebSocketManager::new(config);
+    let ws_chains: Vec<&Chain> = chains
+        .iter()
+        .filter(|c| c.connection_type == ConnectionType::WebSocket)
+        .sorted_by(|a, b| a.network().cmp(b.network()))
+        .collect();
+
+    let mut all_stats = Vec::new();
+    let mut reconnects = Vec::new();
+
+    for kind in kinds {
+        println!(
+            "订阅测试: {} ({} 个端点, {} 秒)",
+            kind.name(),
+            ws_chains.len(),
+            duration.as_secs()
+        );
+        info!("开始订阅测试: {}", kind.name());
+
+        let captures = future::join_all(
+            ws_chains
+                .iter()
+                .map(|chain| capture(&ws_manager, chain, *kind, logs_filter, duration)),
+        )
+        .await;
+
+        all_stats.extend(score_captures(*kind, &captures));
+        reconnects.extend(captures.into_iter().flat_map(|c| c.reconnects));
+    }
+
+    SubscriptionRun {
+        stats: all_stats,
+        reconnects,
+    }
+}
+
+/// 将订阅统计写入 CSV 文件
+pub fn write_to_csv(stats: &[SubscriptionStats], output_path: &Path) -> Result<()> {
+    let file = File::create(output_path)?;
+    let mut wtr = csv::Writer::from_writer(file);
+
+    wtr.write_record([
+        "chain",
+        "endpoint",
+        "subscription",
+        "notifications",
+        "notifications_per_sec",
+        "unique_count",
+        "duplicate_count",
+        "reference_count",
+        "completeness_percent",
+        "gap_blocks",
+        "reconnects",
+        "error",
+        "proxy",
+    ])?;
+
+    for stat in stats {
+        wtr.write_record([
+            &stat.chain,
+            &stat.endpoint,
+            stat.kind.name(),
+            &stat.notifications.to_string(),
+            &format!("{:.2}", stat.notifications_per_sec),
+            &stat.unique_count.to_string(),
+            &stat.duplicate_count.to_string(),
+            &stat.reference_count.to_string(),
+            &format!("{:.2}", stat.completeness * 100.0),
+            &stat.gap_blocks.to_string(),
+            &stat.reconnects.to_string(),
+            stat.error.as_deref().unwrap_or(""),
+            &stat.proxy,
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// 在控制台中打印订阅统计
+pub fn print_stats(stats: &[SubscriptionStats]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+
+    table.add_row(Row::new(vec![
+        Cell::new("链"),
+        Cell::new("订阅"),
+        Cell::new("通知数"),
+        Cell::new("通知/秒"),
+        Cell::new("去重后"),
+        Cell::new("重复"),
+        Cell::new("参考集"),
+        Cell::new("完整性"),
+        Cell::new("缺失区块"),
+        Cell::new("重连"),
+        Cell::new("错误"),
+    ]));
+
+    for stat in stats {
+        let completeness_color = if stat.completeness >= 0.99 {
+            "Fg=Green"
+        } else if stat.completeness >= 0.9 {
+            "Fg=Yellow"
+        } else {
+            "Fg=Red"
+        };
+
+        table.add_row(Row::new(vec![
+            Cell::new(&stat.chain),
+            Cell::new(stat.kind.name()),
+            Cell::new(&stat.notifications.to_string()),
+            Cell::new(&format!("{:.2}", stat.notifications_per_sec)),
+            Cell::new(&stat.unique_count.to_string()),
+            Cell::new(&stat.duplicate_count.to_string()),
+            Cell::new(&stat.reference_count.to_string()),
+            Cell::new(&format!("{:.2}%", stat.completeness * 100.0)).style_spec(completeness_color),
+            Cell::new(&stat.gap_blocks.to_string()),
+            Cell::new(&stat.reconnects.to_string()),
+            Cell::new(stat.error.as_deref().unwrap_or("")),
+        ]));
+    }
+
+    table.printstd();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 在 `blocks` 中的每个区块各收到 `copies` 次 newHeads 通知
+    fn capture(chain: &str, blocks: std::ops::RangeInclusive<u64>, copies: usize) -> Capture {
+        let deliveries: HashMap<String, (usize, Option<u64>)> = blocks
+            .map(|block| (format!("0x{:064x}", block), (copies, Some(block))))
+            .collect();
+        Capture {
+            chain: chain.to_string(),
+            network: "ETH".to_string(),
+            endpoint: format!("wss://{}", chain),
+            proxy: String::new(),
+            notifications: deliveries.len() * copies,
+            deliveries,
+            elapsed_secs: 10.0,
+            reconnects: Vec::new(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn stalled_endpoint_loses_its_missing_tail() {
+        let stats = score_captures(
+            SubscriptionKind::NewHeads,
+            &[capture("ETH-WS", 1..=10, 1), capture("ETH-WS-2", 1..=5, 1)],
+        );
+        assert_eq!(stats[0].reference_count, 10);
+        assert_eq!(stats[0].completeness, 1.0);
+        assert_eq!(stats[1].completeness, 0.5);
+        assert_eq!(stats[1].gap_blocks, 5);
+    }
+
+    #[test]
+    fn late_starting_endpoint_is_compared_from_common_start() {
+        let stats = score_captures(
+            SubscriptionKind::NewHeads,
+            &[capture("ETH-WS", 1..=10, 1), capture("ETH-WS-2", 4..=10, 1)],
+        );
+        assert_eq!(stats[0].reference_count, 7);
+        assert_eq!(stats[0].completeness, 1.0);
+        assert_eq!(stats[1].completeness, 1.0);
+        assert_eq!(stats[1].gap_blocks, 0);
+    }
+
+    #[test]
+    fn duplicates_do_not_inflate_completeness() {
+        let stats = score_captures(
+            SubscriptionKind::NewHeads,
+            &[capture("ETH-WS", 1..=10, 2), capture("ETH-WS-2", 1..=10, 1)],
+        );
+        assert_eq!(stats[0].notifications, 20);
+        assert_eq!(stats[0].unique_count, 10);
+        assert_eq!(stats[0].duplicate_count, 10);
+        assert_eq!(stats[0].completeness, 1.0);
+        assert_eq!(stats[1].duplicate_count, 0);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::chains::{check_endpoint_names, split_endpoint_spec, Chain};
+use crate::rpc::RpcError;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// 客户端证书和私钥（PEM，私钥须为 PKCS#8 格式）
+#[derive(Debug, Clone)]
+pub struct ClientIdentity {
+    cert_pem: Vec<u8>,
+    key_pem: Vec<u8>,
+}
+
+/// 一个端点的 TLS 配置
+#[derive(Debug, Clone, Default)]
+pub struct TlsOptions {
+    /// 额外信任的根证书（每个元素为一个 PEM 证书）
+    pub ca_certs: Vec<Vec<u8>>,
+    /// 用于双向 TLS 的客户端证书
+    pub identity: Option<ClientIdentity>,
+    /// 允许的服务端证书 SHA-256 指纹，为空时不校验
+    pub pins: Vec<[u8; 32]>,
+    /// 跳过证书和主机名校验，仅限实验环境使用
+    pub insecure: bool,
+}
+
+impl TlsOptions {
+    /// 报告中显示的 TLS 配置摘要，默认配置时为空
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.ca_certs.is_empty() {
+            parts.push(format!("ca={}", self.ca_certs.len()));
+        }
+        if self.identity.is_some() {
+            parts.push("mtls".to_string());
+        }
+        if !self.pins.is_empty() {
+            parts.push(format!("pins={}", self.pins.len()));
+        }
+        if self.insecure {
+            parts.push("insecure".to_string());
+        }
+        parts.join(",")
+    }
+
+    /// 创建 native-tls 连接器，用于冷连接 HTTP 请求和 WebSocket 连接
+    pub fn native_connector(&self) -> Result<native_tls::TlsConnector, RpcError> {
+        let mut builder = native_tls::TlsConnector::builder();
+        for pem in &self.ca_certs {
+            let cert = native_tls::Certificate::from_pem(pem)
+                .map_err(|e| RpcError::ConfigError(format!("无效的CA证书: {}", e)))?;
+            builder.add_root_certificate(cert);
+        }
+        if let Some(ref identity) = self.identity {
+            let identity = native_tls::Identity::from_pkcs8(&identity.cert_pem, &identity.key_pem)
+                .map_err(|e| RpcError::ConfigError(format!("无效的客户端证书或私钥: {}", e)))?;
+            builder.identity(identity);
+        }
+        if self.insecure {
+            builder.danger_accept_invalid_certs(true);
+            builder.danger_accept_invalid_hostnames(true);
+        }
+        builder
+            .build()
+            .map_err(|e| RpcError::TlsError(format!("创建TLS连接器失败: {}", e)))
+    }
+
+    /// 在已建立的连接上完成 TLS 握手，并在发送任何数据之前校验证书指纹
+    pub async fn handshake<S>(&self, host: &str, stream: S) -> Result<tokio_native_tls::TlsStream<S>, RpcError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let tls_stream = tokio_native_tls::TlsConnector::from(self.native_connector()?)
+            .connect(host, stream)
+            .await
+            .map_err(|e| RpcError::TlsError(format!("TLS握手失败: {}", e)))?;
+        let peer_certificate = tls_stream
+            .get_ref()
+            .peer_certificate()
+            .ok()
+            .flatten()
+            .and_then(|cert| cert.to_der().ok());
+        self.verify_pin(peer_certificate.as_deref())?;
+        Ok(tls_stream)
+    }
+
+    /// 将 TLS 配置应用到 reqwest 客户端
+    ///
+    /// reqwest 无法在握手时校验自定义指纹，配置了证书指纹时启用 `tls_info`，
+    /// 以便在处理每个响应之前校验该连接的对端证书；发送第一个请求前另见 [`crate::probe::check_pin`]。
+    pub fn configure_reqwest(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder, RpcError> {
+        for pem in &self.ca_certs {
+            let cert = reqwest::Certificate::from_pem(pem)
+                .map_err(|e| RpcError::ConfigError(format!("无效的CA证书: {}", e)))?;
+            builder = builder.add_root_certificate(cert);
+        }
+        if let Some(ref identity) = self.identity {
+            let identity = reqwest::Identity::from_pkcs8_pem(&identity.cert_pem, &identity.key_pem)
+                .map_err(|e| RpcError::ConfigError(format!("无效的客户端证书或私钥: {}", e)))?;
+            builder = builder.identity(identity);
+        }
+        if self.insecure {
+            builder = builder
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true);
+        }
+        if !self.pins.is_empty() {
+            builder = builder.tls_info(true);
+        }
+        Ok(builder)
+    }
+
+    /// 校验对端证书（DER）的 SHA-256 指纹，未配置指纹时直接通过
+    pub fn verify_pin(&self, peer_certificate: Option<&[u8]>) -> Result<(), RpcError> {
+        if self.pins.is_empty() {
+            return Ok(());
+        }
+        let der = peer_certificate.ok_or_else(|| RpcError::TlsError("无法获取对端证书，证书指纹校验失败".to_string()))?;
+        let fingerprint: [u8; 32] = Sha256::digest(der).into();
+        if self.pins.contains(&fingerprint) {
+            Ok(())
+        } else {
+            Err(RpcError::TlsError(format!("证书指纹不匹配: {}", to_hex(&fingerprint))))
+        }
+    }
+}
+
+/// `--tls-*` 命令行参数，每项格式为 `值` (所有端点) 或 `端点名称=值`
+#[derive(Debug)]
+pub struct TlsSpecs<'a> {
+    pub ca: &'a [String],
+    pub client_cert: &'a [String],
+    pub client_key: &'a [String],
+    pub pins: &'a [String],
+    pub insecure: &'a [String],
+}
+
+/// 按命令行参数为每个端点设置 TLS 配置
+///
+/// CA 证书在默认值基础上累加；客户端证书、私钥和证书指纹按端点指定时覆盖默认值。
+/// `--tls-insecure` 的值为端点名称或 `all`。
+pub fn apply_tls_specs(chains: &mut [Chain], specs: &TlsSpecs) -> Result<()> {
+    for (option, values) in [
+        ("--tls-ca", specs.ca),
+        ("--tls-client-cert", specs.client_cert),
+        ("--tls-client-key", specs.client_key),
+        ("--tls-pin", specs.pins),
+    ] {
+        check_endpoint_names(chains, values.iter().filter_map(|v| split_endpoint_spec(v).0), option)?;
+    }
+    check_endpoint_names(chains, specs.insecure.iter().map(|s| s.as_str()).filter(|s| *s != "all"), "--tls-insecure")?;
+
+    for chain in chains.iter_mut() {
+        let mut options = TlsOptions::default();
+
+        for (_, path) in applicable(specs.ca, &chain.name, true) {
+            options.ca_certs.extend(load_ca_bundle(path)?);
+        }
+
+        let cert = applicable(specs.client_cert, &chain.name, false).pop();
+        let key = applicable(specs.client_key, &chain.name, false).pop();
+        options.identity = match (cert, key) {
+            (Some((_, cert)), Some((_, key))) => Some(ClientIdentity {
+                cert_pem: std::fs::read(cert).with_context(|| format!("读取客户端证书失败: {}", cert))?,
+                key_pem: std::fs::read(key).with_context(|| format!("读取客户端私钥失败: {}", key))?,
+            }),
+            (None, None) => None,
+            _ => anyhow::bail!("端点 {} 的客户端证书和私钥必须同时指定", chain.name),
+        };
+
+        options.pins = applicable(specs.pins, &chain.name, false)
+            .into_iter()
+            .map(|(_, pin)| parse_pin(pin))
+            .collect::<Result<_>>()?;
+
+        options.insecure = specs.insecure.iter().any(|s| s == "all" || *s == chain.name);
+
+        chain.tls = options;
+    }
+
+    Ok(())
+}
+
+/// 选出适用于某端点的参数值
+///
+/// `accumulate` 为 false 时，只要存在按端点指定的值就忽略默认值。
+fn applicable<'a>(values: &'a [String], endpoint: &str, accumulate: bool) -> Vec<(Option<&'a str>, &'a str)> {
+    let specs: Vec<_> = values.iter().map(|v| split_endpoint_spec(v)).collect();
+    let has_specific = specs.iter().any(|(name, _)| *name == Some(endpoint));
+    specs
+        .into_iter()
+        .filter(|(name, _)| match name {
+            Some(name) => *name == endpoint,
+            None => accumulate || !has_specific,
+        })
+        .collect()
+}
+
+/// 读取 PEM 格式的 CA 证书包，拆分为单个证书
+fn load_ca_bundle(path: &str) -> Result<Vec<Vec<u8>>> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+
+    let content = std::fs::read_to_string(path).with_context(|| format!("读取CA证书失败: {}", path))?;
+    let mut certs = Vec::new();
+    let mut rest = content.as_str();
+    while let Some(start) = rest.find(BEGIN) {
+        let end = rest[start..]
+            .find(END)
+            .with_context(|| format!("CA证书格式无效: {}", path))?
+            + start
+            + END.len();
+        certs.push(rest.as_bytes()[start..end].to_vec());
+        rest = &rest[end..];
+    }
+
+    if certs.is_empty() {
+        anyhow::bail!("CA证书文件中没有PEM证书: {}", path);
+    }
+    Ok(certs)
+}
+
+/// 解析十六进制的 SHA-256 证书指纹，允许以 `:` 分隔
+fn parse_pin(pin: &str) -> Result<[u8; 32]> {
+    let hex: String = pin.chars().filter(|c| *c != ':').collect();
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("无效的证书指纹 (需要64位十六进制SHA-256): {}", pin);
+    }
+    let mut fingerprint = [0u8; 32];
+    for (i, byte) in fingerprint.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)?;
+    }
+    Ok(fingerprint)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 判断错误链中是否包含 TLS 错误
+pub fn is_tls_error(error: &(dyn Error + 'static)) -> bool {
+    let mut source = Some(error);
+    while let Some(e) = source {
+        if e.downcast_ref::<native_tls::Error>().is_some() {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
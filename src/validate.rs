@@ -0,0 +1,275 @@
+use serde_json::Value;
+
+/// 区块对象必须包含的字段
+const BLOCK_FIELDS: [&str; 4] = ["hash", "parentHash", "number", "timestamp"];
+
+/// JSON-RPC 方法 `result` 的校验规则
+///
+/// 节点返回了没有 `error` 的响应并不代表结果可用，例如 `result` 为 null 或格式错误。
+/// 校验失败的调用不计为成功，单独统计为 `invalid_result`。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Validator {
+    /// 十六进制数量，例如 `0x1b4`（不允许前导零）
+    Quantity,
+    /// 十六进制字节串，`Some(n)` 时必须恰好为 n 字节
+    Data(Option<usize>),
+    /// 已知合约的返回数据，以太坊主网上必须非空
+    ContractData,
+    /// 20 字节地址
+    Address,
+    /// 地址数组
+    Addresses,
+    /// 布尔值
+    Bool,
+    /// 非空字符串
+    Text,
+    /// 十进制数字字符串（net_version）
+    DecimalText,
+    /// 未同步时为 false，同步中为包含 currentBlock 和 highestBlock 的对象
+    SyncStatus,
+    /// 包含 hash、parentHash、number 和 timestamp 的区块对象
+    Block,
+    /// 包含 oldestBlock 和 baseFeePerGas 的对象
+    FeeHistory,
+    /// 日志对象数组
+    Logs,
+    /// 字符串数组
+    Strings,
+    /// 数组（元素不限）
+    Array,
+}
+
+impl Validator {
+    /// 方法对应的校验规则，没有规则的方法不校验
+    pub fn for_method(method: &str) -> Option<Validator> {
+        let validator = match method {
+            "web3_clientVersion" | "eth_protocolVersion" => Validator::Text,
+            "web3_sha3" => Validator::Data(Some(32)),
+            "net_version" => Validator::DecimalText,
+            "net_listening" | "eth_mining" => Validator::Bool,
+            "net_peerCount"
+            | "eth_hashrate"
+            | "eth_gasPrice"
+            | "eth_blockNumber"
+            | "eth_chainId"
+            | "eth_getBalance"
+            | "eth_getTransactionCount"
+            | "eth_getBlockTransactionCountByNumber"
+            | "eth_getUncleCountByBlockNumber"
+            | "eth_estimateGas" => Validator::Quantity,
+            "eth_syncing" => Validator::SyncStatus,
+            "eth_coinbase" => Validator::Address,
+            "eth_accounts" => Validator::Addresses,
+            "eth_getBlockByNumber" => Validator::Block,
+            "eth_getCode" | "eth_call" => Validator::ContractData,
+            "eth_getStorageAt" => Validator::Data(Some(32)),
+            "eth_feeHistory" => Validator::FeeHistory,
+            "eth_getLogs" => Validator::Logs,
+            "engine_exchangeCapabilities" => Validator::Strings,
+            "engine_getPayloadBodiesByRangeV1" => Validator::Array,
+            _ => return None,
+        };
+        Some(validator)
+    }
+
+    /// 校验 `result`，失败时返回原因
+    ///
+    /// `network` 为端点所属的网络（例如 ETH），测试用的合约只部署在以太坊上，
+    /// 其他网络上 [`Validator::ContractData`] 只检查格式。
+    pub fn check(&self, result: &Value, network: &str) -> Result<(), String> {
+        match self {
+            Validator::Quantity => check_quantity(result, "result"),
+            Validator::Data(len) => check_data(result, "result", *len),
+            Validator::ContractData => {
+                check_data(result, "result", None)?;
+                if network == "ETH" && result.as_str() == Some("0x") {
+                    return Err("已知合约返回了空数据".to_string());
+                }
+                Ok(())
+            }
+            Validator::Address => check_data(result, "result", Some(20)),
+            Validator::Addresses => check_array(result, |i, item| check_data(item, &format!("result[{}]", i), Some(20))),
+            Validator::Bool => match result {
+                Value::Bool(_) => Ok(()),
+                other => Err(format!("result 应为布尔值: {}", excerpt(other))),
+            },
+            Validator::Text => match result.as_str() {
+                Some(s) if !s.is_empty() => Ok(()),
+                _ => Err(format!("result 应为非空字符串: {}", excerpt(result))),
+            },
+            Validator::DecimalText => match result.as_str() {
+                Some(s) if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) => Ok(()),
+                _ => Err(format!("result 应为十进制数字字符串: {}", excerpt(result))),
+            },
+            Validator::SyncStatus => match result {
+                Value::Bool(false) => Ok(()),
+                Value::Object(_) => {
+                    check_quantity(field(result, "currentBlock")?, "currentBlock")?;
+                    check_quantity(field(result, "highestBlock")?, "highestBlock")
+                }
+                other => Err(format!("result 应为 false 或同步状态对象: {}", excerpt(other))),
+            },
+            Validator::Block => {
+                if !result.is_object() {
+                    return Err(format!("result 应为区块对象: {}", excerpt(result)));
+                }
+                for name in BLOCK_FIELDS {
+                    let value = field(result, name)?;
+                    match name {
+                        "hash" | "parentHash" => check_data(value, name, Some(32))?,
+                        _ => check_quantity(value, name)?,
+                    }
+                }
+                Ok(())
+            }
+            Validator::FeeHistory => {
+                if !result.is_object() {
+                    return Err(format!("result 应为对象: {}", excerpt(result)));
+                }
+                check_quantity(field(result, "oldestBlock")?, "oldestBlock")?;
+                check_array(field(result, "baseFeePerGas")?, |i, item| check_quantity(item, &format!("baseFeePerGas[{}]", i)))
+            }
+            Validator::Logs => check_array(result, |i, item| match item {
+                Value::Object(_) => Ok(()),
+                other => Err(format!("result[{}] 应为日志对象: {}", i, excerpt(other))),
+            }),
+            Validator::Strings => check_array(result, |i, item| match item {
+                Value::String(_) => Ok(()),
+                other => Err(format!("result[{}] 应为字符串: {}", i, excerpt(other))),
+            }),
+            Validator::Array => check_array(result, |_, _| Ok(())),
+        }
+    }
+}
+
+fn field<'a>(object: &'a Value, name: &str) -> Result<&'a Value, String> {
+    match object.get(name) {
+        Some(value) if !value.is_null() => Ok(value),
+        _ => Err(format!("缺少字段 {}", name)),
+    }
+}
+
+/// 解析十六进制数量，要求恰好一个 `0x` 前缀和至少一位十六进制数字，超出 u64 范围时返回 None
+pub(crate) fn parse_quantity(value: &Value) -> Option<u64> {
+    let digits = value.as_str()?.strip_prefix("0x")?;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u64::from_str_radix(digits, 16).ok()
+}
+
+fn check_quantity(value: &Value, name: &str) -> Result<(), String> {
+    let valid = value
+        .as_str()
+        .and_then(|s| s.strip_prefix("0x"))
+        .is_some_and(|digits| {
+            !digits.is_empty()
+                && digits.bytes().all(|b| b.is_ascii_hexdigit())
+                && (digits == "0" || !digits.starts_with('0'))
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("{} 应为十六进制数量: {}", name, excerpt(value)))
+    }
+}
+
+fn check_data(value: &Value, name: &str, len: Option<usize>) -> Result<(), String> {
+    let digits = value
+        .as_str()
+        .and_then(|s| s.strip_prefix("0x"))
+        .filter(|digits| digits.len() % 2 == 0 && digits.bytes().all(|b| b.is_ascii_hexdigit()))
+        .ok_or_else(|| format!("{} 应为十六进制字节串: {}", name, excerpt(value)))?;
+    match len {
+        Some(len) if digits.len() != len * 2 => {
+            Err(format!("{} 应为 {} 字节，实际为 {} 字节", name, len, digits.len() / 2))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_array(value: &Value, check_item: impl Fn(usize, &Value) -> Result<(), String>) -> Result<(), String> {
+    let items = value
+        .as_array()
+        .ok_or_else(|| format!("result 应为数组: {}", excerpt(value)))?;
+    items.iter().enumerate().try_for_each(|(i, item)| check_item(i, item))
+}
+
+/// 错误信息中显示的值，过长时截断
+pub fn excerpt(value: &Value) -> String {
+    const MAX_CHARS: usize = 80;
+    let text = value.to_string();
+    match text.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn well_formed_block_passes() {
+        let block = json!({
+            "hash": format!("0x{}", "ab".repeat(32)),
+            "parentHash": format!("0x{}", "cd".repeat(32)),
+            "number": "0x10d4f",
+            "timestamp": "0x65a1b2c3",
+            "transactions": []
+        });
+        assert_eq!(Validator::Block.check(&block, "ETH"), Ok(()));
+    }
+
+    #[test]
+    fn block_missing_field_fails() {
+        let block = json!({ "hash": format!("0x{}", "ab".repeat(32)), "number": "0x1" });
+        assert!(Validator::Block.check(&block, "ETH").unwrap_err().contains("parentHash"));
+    }
+
+    #[test]
+    fn quantity_requires_prefix_and_no_leading_zero() {
+        assert_eq!(check_quantity(&json!("0x0"), "result"), Ok(()));
+        assert_eq!(check_quantity(&json!("0x1b4"), "result"), Ok(()));
+        assert!(check_quantity(&json!("1b4"), "result").is_err());
+        assert!(check_quantity(&json!("0x01b4"), "result").is_err());
+        assert!(check_quantity(&json!("0x"), "result").is_err());
+        assert!(check_quantity(&json!(436), "result").is_err());
+    }
+
+    #[test]
+    fn parse_quantity_requires_single_prefix_and_digits() {
+        assert_eq!(parse_quantity(&json!("0x0")), Some(0));
+        assert_eq!(parse_quantity(&json!("0x1b4")), Some(0x1b4));
+        assert_eq!(parse_quantity(&json!("1b4")), None);
+        assert_eq!(parse_quantity(&json!("0x0x1b4")), None);
+        assert_eq!(parse_quantity(&json!("0x")), None);
+        assert_eq!(parse_quantity(&json!("0x+1")), None);
+        assert_eq!(parse_quantity(&json!(format!("0x1{}", "0".repeat(16)))), None);
+        assert_eq!(parse_quantity(&json!(436)), None);
+    }
+
+    #[test]
+    fn data_of_wrong_length_fails() {
+        let hash = format!("0x{}", "00".repeat(32));
+        assert_eq!(check_data(&json!(hash), "result", Some(32)), Ok(()));
+        let err = check_data(&json!(format!("0x{}", "00".repeat(20))), "result", Some(32)).unwrap_err();
+        assert!(err.contains("实际为 20 字节"), "{}", err);
+        assert!(check_data(&json!("0x123"), "result", None).is_err());
+    }
+
+    #[test]
+    fn array_item_error_reports_index() {
+        let address = format!("0x{}", "11".repeat(20));
+        let addresses = json!([address, address, "0x1234"]);
+        let err = Validator::Addresses.check(&addresses, "ETH").unwrap_err();
+        assert!(err.starts_with("result[2]"), "{}", err);
+    }
+
+    #[test]
+    fn contract_data_must_be_non_empty_on_eth_only() {
+        assert!(Validator::ContractData.check(&json!("0x"), "ETH").is_err());
+        assert_eq!(Validator::ContractData.check(&json!("0x"), "BSC"), Ok(()));
+    }
+}